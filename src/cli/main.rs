@@ -1,23 +1,595 @@
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+#[cfg(unix)]
+use std::os::unix::io::FromRawFd;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 
-use ltc_reader::connect_stream;
+use clap::{CommandFactory, Parser, Subcommand};
+use cpal::traits::{DeviceTrait, HostTrait};
+use cpal::BufferSize;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let host = cpal::default_host();
-    let device = host.default_input_device().expect("no input device available");
-    let default_config = device.default_input_config().expect("no supported config");
-    let stream = connect_stream(
+#[cfg(feature = "media")]
+use ltc_reader::MediaReader;
+use ltc_reader::{analyze, connect_channel, connect_recorder, decode_source, decode_source_parallel, locate, split_segments, stamp_bwf, sync, write_subtitles, BwfTimecode, DecodedFrame, DecoderOptions, Error, LtcEvent, PcmFormat, RawReader, SampleSource, SourceEvents, InputConfig, SubtitleFormat, TimecodeFormat, TimecodeIndex, WavReader};
+
+/// Reads LTC timecode from an audio input, a file, or raw PCM on stdin.
+#[derive(Parser)]
+#[command(version, after_help = "\
+Exit codes:
+  1  the input device failed while running
+  2  invalid arguments
+  3  no usable input device
+  4  the input device has no usable configuration
+  5  the input stream could not be started
+  6  the input file could not be read
+  7  no timecode found in the input")]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// How to print timecode: smpte, frames, seconds, or a template like "%H:%M:%S%;%F"
+    #[arg(long, global = true, default_value = "smpte")]
+    format: TimecodeFormat,
+    /// Decode a WAV or BWF file instead of the audio input, or raw PCM from stdin with "-".
+    /// Frames are printed with their sample offset in the input. With the media feature, FLAC,
+    /// MP3, MOV and MP4 files work too
+    #[arg(long, short, global = true)]
+    input: Option<PathBuf>,
+    /// Decode raw PCM from this open file descriptor instead of the audio input. Descriptors 0
+    /// to 2 are not accepted, use --input - for stdin
+    #[cfg(unix)]
+    #[arg(long, global = true, conflicts_with = "input", value_parser = clap::value_parser!(i32).range(3..))]
+    fd: Option<i32>,
+    /// Read the input as raw PCM in this sample format: u8, s8, s16le, s16be, s24le, s24be,
+    /// s32le, s32be, f32le, f32be, f64le or f64be. Stdin and --fd are raw s16le by default
+    #[arg(long, global = true)]
+    raw: Option<PcmFormat>,
+    /// Sample rate of raw input, 48000 by default. For the audio input, the rate to open the
+    /// device with instead of its default rate
+    #[arg(long, global = true)]
+    sample_rate: Option<u32>,
+    /// Number of interleaved channels of raw input
+    #[arg(long, global = true, default_value_t = 1)]
+    channels: u16,
+    /// Channel of the input to decode, counting from 0
+    #[arg(long, global = true, default_value_t = 0)]
+    channel: u16,
+    /// Audio track of the input file to decode, counting from 0
+    #[cfg(feature = "media")]
+    #[arg(long, global = true, default_value_t = 0)]
+    track: usize,
+    /// Decode the input on this many threads, 0 for one per core. The result is the same as
+    /// with a single thread
+    #[arg(long, short, global = true, default_value_t = 1)]
+    jobs: usize,
+    /// List the audio hosts and their input devices with channel counts and sample rates
+    #[arg(long)]
+    list_devices: bool,
+    /// Audio host to use, e.g. ALSA, JACK, CoreAudio or WASAPI, the default host otherwise
+    #[arg(long, global = true)]
+    host: Option<String>,
+    /// Input device to use, by name or by index as shown by --list-devices, the default input
+    /// device otherwise
+    #[arg(long, short, global = true)]
+    device: Option<String>,
+    /// Buffer size of the audio input in frames, chosen by the host otherwise
+    #[arg(long, global = true)]
+    buffer_size: Option<u32>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Scans a whole recording and reports its first and last timecode, frame rate and speed,
+    /// discontinuities, dropouts, parity and biphase errors and userdata changes
+    Analyze {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Decodes the LTC channel of a WAV file and writes a copy of it whose BWF time reference
+    /// is the time of day of its first sample, so editors can sync it by timecode
+    Stamp {
+        /// Where to write the stamped file
+        #[arg(long, short)]
+        output: PathBuf,
+        /// Write the timecode to an iXML chunk as well
+        #[arg(long)]
+        ixml: bool,
+    },
+    /// Decodes the LTC of several files and prints where each one starts on a common timeline,
+    /// beginning with the earliest file at the frame rate of the first one, as CSV. Files that
+    /// overlap and timecode rates that differ from the timeline are flagged
+    Sync {
+        /// The files to sync, decoded like --input
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Print the timeline as JSON
+        #[arg(long, conflicts_with = "edl")]
+        json: bool,
+        /// Print the timeline as a CMX3600 EDL
+        #[arg(long)]
+        edl: bool,
+    },
+    /// Splits a recording into segments of continuous timecode and prints their timecode,
+    /// sample range and frame rate as CSV
+    Segments {
+        /// Print the segments as JSON
+        #[arg(long, conflicts_with = "edl")]
+        json: bool,
+        /// Print the segments as a CMX3600 EDL, with the input file name as reel name
+        #[arg(long)]
+        edl: bool,
+    },
+    /// Writes an index of the timecode in a recording, from which the library maps timecode to
+    /// sample positions and back without decoding the recording again
+    Index {
+        /// Where to write the index, the input path with ".ltcindex" appended by default
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Prints a subtitle track that shows the timecode of a recording in sync with it, to
+    /// overlay the source timecode on a proxy. Timecode is written in --format
+    Subtitles {
+        /// Subtitle format: vtt or srt
+        #[arg(long = "subtitle-format", default_value = "vtt")]
+        subtitle_format: SubtitleFormat,
+        /// Write the subtitles to this file instead of printing them
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Records the audio input to a broadcast wave file while decoding LTC from it, until Enter
+    /// is pressed. The time of day of the first sample is written to the time reference of the
+    /// file, and discontinuities and dropouts of the timecode to a CSV file next to it
+    Record {
+        /// The file to record to
+        #[arg(long, short)]
+        output: PathBuf,
+        /// Stop after this many seconds
+        #[arg(long)]
+        duration: Option<u64>,
+    },
+}
+
+fn exit_code(err: &Error) -> u8 {
+    match err {
+        Error::Stream(_) | Error::Lagged(_) => 1,
+        Error::NoDevice | Error::Devices(_) => 3,
+        Error::Config(_) | Error::SupportedConfigs(_) | Error::UnsupportedSampleFormat(_) | Error::UnsupportedSampleRate(_) => 4,
+        Error::BuildStream(_) | Error::PlayStream(_) | Error::PauseStream(_) => 5,
+        Error::NoChannel { .. } | Error::NoTrack { .. } => 2,
+        Error::Io(_) | Error::InvalidFile(_) => 6,
+        Error::NoTimecode => 7,
+        #[cfg(feature = "media")]
+        Error::Media(_) => 6,
+    }
+}
+
+fn print_event(event: LtcEvent, format: &TimecodeFormat) {
+    match event {
+        LtcEvent::Frame(decoded) => {
+            println!("{:} fps {:}", decoded.fps, format.format(&decoded.frame.timecode(), decoded.fps));
+        }
+        LtcEvent::Discontinuity { continuity, .. } => {
+            println!("discontinuity: {:?}", continuity);
+        }
+        LtcEvent::Locked { .. } => {
+            println!("signal locked");
+        }
+        LtcEvent::SignalLost { .. } => {
+            println!("signal lost");
+        }
+    }
+}
+
+fn open_file(path: &Path, args: &Args) -> Result<Box<dyn SampleSource>, Error> {
+    #[cfg(feature = "media")]
+    {
+        let extension = path.extension().and_then(|it| it.to_str()).unwrap_or("").to_ascii_lowercase();
+        if !matches!(extension.as_str(), "wav" | "wave" | "bwf") {
+            let mut reader = MediaReader::open(path)?;
+            reader.set_track(args.track)?;
+            reader.set_channel(args.channel)?;
+            return Ok(Box::new(reader));
+        }
+    }
+    let mut reader = WavReader::open(path)?;
+    reader.set_channel(args.channel)?;
+    Ok(Box::new(reader))
+}
+
+fn open_raw(reader: Box<dyn Read>, args: &Args) -> Result<Box<dyn SampleSource>, Error> {
+    let format = args.raw.unwrap_or(PcmFormat::S16Le);
+    let mut reader = RawReader::new(reader, format, args.sample_rate.unwrap_or(48000), args.channels);
+    reader.set_channel(args.channel)?;
+    Ok(Box::new(reader))
+}
+
+/// Opens the file, pipe or file descriptor given on the command line, `None` means the audio
+/// input.
+fn open_input(args: &Args) -> Result<Option<Box<dyn SampleSource>>, Error> {
+    #[cfg(unix)]
+    if let Some(fd) = args.fd {
+        // SAFETY: stdin, stdout and stderr are rejected by the argument parser, any other
+        // descriptor was handed to us to read from and nothing else in the process uses it.
+        let file = unsafe { File::from_raw_fd(fd) };
+        return open_raw(Box::new(file), args).map(Some);
+    }
+    match &args.input {
+        None => Ok(None),
+        Some(path) if path.as_os_str() == "-" => open_raw(Box::new(std::io::stdin().lock()), args).map(Some),
+        Some(path) if args.raw.is_some() => open_raw(Box::new(BufReader::new(File::open(path)?)), args).map(Some),
+        Some(path) => open_file(path, args).map(Some),
+    }
+}
+
+/// Decodes all of `reader` on as many threads as `--jobs` asks for.
+fn decode_events<S: SampleSource + 'static>(reader: S, options: DecoderOptions, args: &Args) -> Box<dyn SourceEvents> {
+    match args.jobs {
+        1 => Box::new(decode_source(reader, options)),
+        jobs => Box::new(decode_source_parallel(reader, options, jobs)),
+    }
+}
+
+fn decode_input(reader: Box<dyn SampleSource>, args: &Args) -> Result<(), Error> {
+    for event in decode_events(reader, DecoderOptions::default(), args) {
+        let event = event?;
+        if let LtcEvent::Frame(decoded) = &event {
+            print!("{:} ", decoded.position);
+        }
+        print_event(event, &args.format);
+    }
+    Ok(())
+}
+
+fn analyze_input(args: &Args, json: bool) -> Result<(), Error> {
+    let Some(reader) = open_input(args)? else {
+        Args::command()
+            .error(clap::error::ErrorKind::MissingRequiredArgument, "analyze needs --input or --fd")
+            .exit();
+    };
+    let options = DecoderOptions {
+        signal_timeout: Some(reader.sample_rate() as u64 / 2),
+        ..DecoderOptions::default()
+    };
+    let analysis = analyze(decode_events(reader, options, args))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&analysis).expect("analysis is always valid JSON"));
+    } else {
+        print!("{}", analysis.report(&args.format));
+    }
+    Ok(())
+}
+
+fn stamp_input(args: &Args, output: &Path, ixml: bool) -> Result<(), Error> {
+    let Some(path) = &args.input else {
+        Args::command()
+            .error(clap::error::ErrorKind::MissingRequiredArgument, "stamp needs --input")
+            .exit();
+    };
+    let mut reader = WavReader::open(path)?;
+    reader.set_channel(args.channel)?;
+    let sample_rate = reader.format().sample_rate;
+    let frames = decode_events(reader, DecoderOptions::default(), args)
+        .filter_map(|event| event.map(LtcEvent::into_frame).transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+    let timecode = BwfTimecode::from_frames(&frames, sample_rate).ok_or(Error::NoTimecode)?;
+    println!("time reference {} samples at {} Hz", timecode.time_reference, sample_rate);
+    // Written next to the output and moved into place once complete, so the input can be
+    // stamped in place and is never truncated before it was copied.
+    let mut temporary = output.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+    let mut input = BufReader::new(File::open(path)?);
+    let result = File::create(&temporary).map_err(Error::from).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        stamp_bwf(&mut input, &mut writer, &timecode, ixml)?;
+        writer.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+        Ok(std::fs::rename(&temporary, output)?)
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temporary);
+    }
+    result
+}
+
+fn sync_files(args: &Args, files: &[PathBuf], json: bool, edl: bool) -> Result<(), Error> {
+    let recordings = files.iter()
+        .map(|path| locate(path.display().to_string(), decode_events(open_file(path, args)?, DecoderOptions::default(), args)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let report = sync(&recordings);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).expect("sync report is always valid JSON"));
+    } else if edl {
+        print!("{}", report.edl("SYNC"));
+    } else {
+        print!("{}", report.csv(&args.format));
+    }
+    Ok(())
+}
+
+fn segment_input(args: &Args, json: bool, edl: bool) -> Result<(), Error> {
+    let Some(reader) = open_input(args)? else {
+        Args::command()
+            .error(clap::error::ErrorKind::MissingRequiredArgument, "segments needs --input or --fd")
+            .exit();
+    };
+    let segments = split_segments(decode_events(reader, DecoderOptions::default(), args))?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&segments).expect("segments are always valid JSON"));
+    } else if edl {
+        let reel = args.input.as_deref()
+            .and_then(Path::file_stem)
+            .and_then(|it| it.to_str())
+            .filter(|it| *it != "-")
+            .unwrap_or("AX");
+        print!("{}", segments.edl("SEGMENTS", reel));
+    } else {
+        print!("{}", segments.csv(&args.format));
+    }
+    Ok(())
+}
+
+fn index_input(args: &Args, output: Option<&Path>) -> Result<(), Error> {
+    let output = match (output, &args.input) {
+        (Some(output), _) => output.to_path_buf(),
+        (None, Some(input)) if input.as_os_str() != "-" => {
+            let mut output = input.clone().into_os_string();
+            output.push(".ltcindex");
+            PathBuf::from(output)
+        }
+        _ => Args::command()
+            .error(clap::error::ErrorKind::MissingRequiredArgument, "index needs --output unless --input is a file")
+            .exit(),
+    };
+    let Some(reader) = open_input(args)? else {
+        Args::command()
+            .error(clap::error::ErrorKind::MissingRequiredArgument, "index needs --input or --fd")
+            .exit();
+    };
+    let index = TimecodeIndex::build(decode_events(reader, DecoderOptions::default(), args))?;
+    index.save(&output)?;
+    println!("{} segments written to {}", index.segments().len(), output.display());
+    Ok(())
+}
+
+fn subtitle_input(args: &Args, subtitle_format: SubtitleFormat, output: Option<&Path>) -> Result<(), Error> {
+    let Some(reader) = open_input(args)? else {
+        Args::command()
+            .error(clap::error::ErrorKind::MissingRequiredArgument, "subtitles needs --input or --fd")
+            .exit();
+    };
+    let sample_rate = reader.sample_rate();
+    let frames = decode_events(reader, DecoderOptions::default(), args)
+        .filter_map(|event| event.map(LtcEvent::into_frame).transpose())
+        .collect::<Result<Vec<_>, _>>()?;
+    let subtitles = write_subtitles(&frames, sample_rate, subtitle_format, &args.format);
+    match output {
+        Some(output) => std::fs::write(output, subtitles)?,
+        None => print!("{}", subtitles),
+    }
+    Ok(())
+}
+
+fn list_devices() {
+    let default_host = cpal::default_host().id();
+    for id in cpal::available_hosts() {
+        println!("{}{}", id.name(), if id == default_host { " (default)" } else { "" });
+        let host = match cpal::host_from_id(id) {
+            Ok(host) => host,
+            Err(err) => {
+                println!("  unavailable: {}", err);
+                continue;
+            }
+        };
+        let devices = match host.input_devices() {
+            Ok(devices) => devices,
+            Err(err) => {
+                println!("  unavailable: {}", err);
+                continue;
+            }
+        };
+        let default_device = host.default_input_device().and_then(|it| it.name().ok());
+        for (index, device) in devices.enumerate() {
+            let name = device.name().unwrap_or_else(|_| "unknown".to_string());
+            let default = if default_device.as_ref() == Some(&name) { " (default)" } else { "" };
+            let configs: Vec<_> = device.supported_input_configs().map(Iterator::collect).unwrap_or_default();
+            let mut channels: Vec<u16> = configs.iter().map(|it| it.channels()).collect();
+            channels.sort_unstable();
+            channels.dedup();
+            let min_rate = configs.iter().map(|it| it.min_sample_rate().0).min();
+            let max_rate = configs.iter().map(|it| it.max_sample_rate().0).max();
+            match (min_rate, max_rate) {
+                (Some(min_rate), Some(max_rate)) => {
+                    let channels: Vec<String> = channels.iter().map(u16::to_string).collect();
+                    println!("  {}: {}{}, {} channels, {}-{} Hz", index, name, default, channels.join("/"), min_rate, max_rate);
+                }
+                _ => println!("  {}: {}{}, no input configurations", index, name, default),
+            }
+        }
+    }
+}
+
+/// Opens the input device and configuration selected on the command line.
+fn open_device(args: &Args) -> Result<(cpal::Device, InputConfig), Error> {
+    let host = match &args.host {
+        None => cpal::default_host(),
+        Some(name) => {
+            let Some(id) = cpal::available_hosts().into_iter().find(|id| id.name().eq_ignore_ascii_case(name)) else {
+                let hosts: Vec<&str> = cpal::available_hosts().iter().map(|id| id.name()).collect();
+                Args::command()
+                    .error(clap::error::ErrorKind::InvalidValue, format!("unknown host \"{}\", available hosts: {}", name, hosts.join(", ")))
+                    .exit();
+            };
+            cpal::host_from_id(id).map_err(|_| Error::NoDevice)?
+        }
+    };
+    let device = match &args.device {
+        None => host.default_input_device(),
+        Some(device) => {
+            let devices: Vec<cpal::Device> = host.input_devices()?.collect();
+            let names: Vec<String> = devices.iter().map(|it| it.name().unwrap_or_default()).collect();
+            let index = device.parse::<usize>().ok()
+                .or_else(|| names.iter().position(|name| name == device))
+                .or_else(|| names.iter().position(|name| name.to_lowercase().contains(&device.to_lowercase())));
+            index.and_then(|index| devices.into_iter().nth(index))
+        }
+    }.ok_or(Error::NoDevice)?;
+
+    let config = match args.sample_rate {
+        None => device.default_input_config()?,
+        Some(rate) => {
+            let default = device.default_input_config().ok();
+            let mut configs: Vec<_> = device.supported_input_configs()?
+                .filter(|it| (it.min_sample_rate().0..=it.max_sample_rate().0).contains(&rate))
+                .collect();
+            // Prefer the sample format and channels the device uses by default.
+            configs.sort_by_key(|it| default.as_ref()
+                .map(|default| (it.sample_format() != default.sample_format(), it.channels() != default.channels())));
+            configs.into_iter().next()
+                .ok_or(Error::UnsupportedSampleRate(rate))?
+                .with_sample_rate(cpal::SampleRate(rate))
+        }
+    };
+    Ok((device, InputConfig {
+        config,
+        channel: args.channel,
+        buffer_size: args.buffer_size.map_or(BufferSize::Default, BufferSize::Fixed),
+    }))
+}
+
+/// Messages to the main thread while recording.
+enum RecordMessage {
+    Event(LtcEvent),
+    Error(Error),
+    Stop,
+}
+
+fn record(args: &Args, output: &Path, duration: Option<u64>) -> Result<(), Error> {
+    let (device, config) = open_device(args)?;
+    let channels = config.config.channels();
+    let sample_rate = config.config.sample_rate().0;
+    let options = DecoderOptions {
+        signal_timeout: Some(sample_rate as u64 / 2),
+        ..DecoderOptions::default()
+    };
+    let sidecar_path = output.with_extension("discontinuities.csv");
+    let mut sidecar = BufWriter::new(File::create(&sidecar_path)?);
+    writeln!(sidecar, "sample,timecode,event")?;
+
+    let (sender, receiver) = mpsc::channel();
+    let error_sender = sender.clone();
+    let stop_sender = sender.clone();
+    thread::spawn(move || {
+        let _ = std::io::stdin().lock().read_line(&mut String::new());
+        let _ = stop_sender.send(RecordMessage::Stop);
+    });
+    let recorder = connect_recorder(
         &device,
-        &default_config.config(),
-        |(frame, fps)| {
-            println!("{:} fps {:}", fps, frame);
-        },
-        |err| {
-            println!("error in stream: {:}", err);
-        },
+        config,
+        options,
+        BufWriter::new(File::create(output)?),
+        move |event| { let _ = sender.send(RecordMessage::Event(event)); },
+        move |err| { let _ = error_sender.send(RecordMessage::Error(err)); },
     )?;
-    stream.play().expect("could not start stream");
-    loop {
-        std::thread::sleep(std::time::Duration::from_secs(1));
+    recorder.play()?;
+    eprintln!("recording {} channels at {} Hz to {}, press Enter to stop", channels, sample_rate, output.display());
+
+    let mut last = None;
+    let mut failure = None;
+    while failure.is_none() && recorder.is_recording() && duration.is_none_or(|it| recorder.len() < it * sample_rate as u64) {
+        match receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok(RecordMessage::Event(event)) => failure = record_event(args, &mut sidecar, &mut last, event).err(),
+            Ok(RecordMessage::Error(Error::Stream(cpal::StreamError::BackendSpecific { err }))) =>
+                eprintln!("error in stream: {}", err),
+            Ok(RecordMessage::Error(err)) => failure = Some(err),
+            Ok(RecordMessage::Stop) => break,
+            Err(_) => {}
+        }
+    }
+    // Finish the file even when the stream failed, then log the events sent before it stopped.
+    let timecode = recorder.finish();
+    for message in receiver.try_iter() {
+        if let (RecordMessage::Event(event), None) = (message, &failure) {
+            failure = record_event(args, &mut sidecar, &mut last, event).err();
+        }
+    }
+    if let Some(err) = failure {
+        return Err(err);
+    }
+    match timecode? {
+        Some(timecode) => println!("time reference {} samples at {} Hz", timecode.time_reference, sample_rate),
+        None => eprintln!("no timecode found, the time reference is 0"),
+    }
+    Ok(())
+}
+
+/// Logs a recorder event to stderr and the discontinuity sidecar.
+fn record_event(args: &Args, sidecar: &mut impl Write, last: &mut Option<DecodedFrame>, event: LtcEvent) -> Result<(), Error> {
+    let (position, timecode, event) = match event {
+        LtcEvent::Frame(frame) => {
+            *last = Some(frame);
+            return Ok(());
+        }
+        LtcEvent::Discontinuity { position, continuity } => (position, last.as_ref(), format!("\"{:?}\"", continuity)),
+        LtcEvent::SignalLost { position } => (position, last.as_ref(), "signal lost".to_string()),
+        LtcEvent::Locked { position } => (position, None, "signal locked".to_string()),
+    };
+    let timecode = timecode
+        .map(|frame| args.format.format(&frame.frame.timecode(), frame.fps))
+        .unwrap_or_default();
+    eprintln!("{} at sample {}: {}", timecode, position, event);
+    writeln!(sidecar, "{},\"{}\",{}", position, timecode, event)?;
+    sidecar.flush()?;
+    Ok(())
+}
+
+fn run(args: Args) -> Result<(), Error> {
+    match &args.command {
+        Some(Command::Analyze { json }) => return analyze_input(&args, *json),
+        Some(Command::Stamp { output, ixml }) => return stamp_input(&args, output, *ixml),
+        Some(Command::Sync { files, json, edl }) => return sync_files(&args, files, *json, *edl),
+        Some(Command::Segments { json, edl }) => return segment_input(&args, *json, *edl),
+        Some(Command::Index { output }) => return index_input(&args, output.as_deref()),
+        Some(Command::Subtitles { subtitle_format, output }) => return subtitle_input(&args, *subtitle_format, output.as_deref()),
+        Some(Command::Record { output, duration }) => return record(&args, output, *duration),
+        None => {}
+    }
+    if args.list_devices {
+        list_devices();
+        return Ok(());
+    }
+    if let Some(reader) = open_input(&args)? {
+        return decode_input(reader, &args);
+    }
+    let (device, config) = open_device(&args)?;
+    let options = DecoderOptions {
+        signal_timeout: Some(config.config.sample_rate().0 as u64 / 2),
+        ..DecoderOptions::default()
+    };
+    let receiver = connect_channel(&device, config, options, 1024)?;
+    receiver.play()?;
+    for item in &receiver {
+        match item {
+            Ok(event) => print_event(event, &args.format),
+            Err(Error::Stream(cpal::StreamError::BackendSpecific { err })) => {
+                eprintln!("error in stream: {}", err);
+            }
+            Err(Error::Lagged(count)) => eprintln!("{} events dropped, output fell behind", count),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::from(exit_code(&err))
+        }
     }
 }
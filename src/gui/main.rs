@@ -2,17 +2,26 @@ use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use gtk::traits::{BoxExt, GtkWindowExt};
 use relm::{channel, ComponentParts, ComponentSender, Receiver, RelmApp, RelmWidgetExt, SimpleComponent};
 
-use ltc_reader::{connect_stream, LtcFrame};
+use ltc_reader::{connect_stream, DecoderOptions, Error, LtcEvent, LtcFrame, TimecodeFormat};
+
+const FORMATS: [(&str, TimecodeFormat); 3] = [
+    ("SMPTE", TimecodeFormat::Smpte),
+    ("Frames", TimecodeFormat::FrameCount),
+    ("Seconds", TimecodeFormat::Seconds),
+];
 
 #[derive(Debug)]
 enum AppInput {
     Update(LtcFrame, u128),
-    Error(cpal::StreamError),
+    SignalLost,
+    SetFormat(TimecodeFormat),
+    Error(Error),
 }
 
 struct AppModel {
     timecode: Option<LtcFrame>,
     fps: Option<u128>,
+    format: TimecodeFormat,
 }
 
 struct AppWidgets {
@@ -46,7 +55,7 @@ impl SimpleComponent for AppModel {
         window: &Self::Root,
         sender: ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        let model = AppModel { timecode: None, fps: None };
+        let model = AppModel { timecode: None, fps: None, format: TimecodeFormat::Smpte };
 
         let vbox = gtk::Box::builder()
             .orientation(gtk::Orientation::Vertical)
@@ -69,11 +78,20 @@ impl SimpleComponent for AppModel {
             )
         );
         label_timecode.set_margin_all(5);
+        let dropdown_format = gtk::DropDown::from_strings(&FORMATS.map(|(name, _)| name));
+        dropdown_format.set_margin_all(5);
+        let format_sender = sender.clone();
+        dropdown_format.connect_selected_notify(move |dropdown| {
+            if let Some((_, format)) = FORMATS.get(dropdown.selected() as usize) {
+                format_sender.input(AppInput::SetFormat(format.clone()));
+            }
+        });
 
         window.set_child(Some(&vbox));
         vbox.set_margin_all(5);
         vbox.append(&label_fps);
         vbox.append(&label_timecode);
+        vbox.append(&dropdown_format);
 
         sender.clone().spawn_oneshot_command(move || {
             while let Some(event) = receiver.recv_sync() {
@@ -92,6 +110,13 @@ impl SimpleComponent for AppModel {
                 self.timecode = Some(timecode);
                 self.fps = Some(fps);
             }
+            AppInput::SignalLost => {
+                self.timecode = None;
+                self.fps = None;
+            }
+            AppInput::SetFormat(format) => {
+                self.format = format;
+            }
             AppInput::Error(_) => {}
         }
     }
@@ -104,7 +129,7 @@ impl SimpleComponent for AppModel {
                 .as_str()
         );
         widgets.label_timecode.set_label(
-            self.timecode.clone().map(|frame| frame.to_string())
+            self.timecode.clone().map(|frame| self.format.format(&frame.timecode(), self.fps.unwrap_or(0)))
                 .unwrap_or("00:00:00:00".to_string())
                 .as_str()
         );
@@ -120,11 +145,22 @@ fn main() {
     let host = cpal::default_host();
     let device = host.default_input_device().expect("no input device available");
     let default_config = device.default_input_config().expect("no supported config");
+    let options = DecoderOptions {
+        signal_timeout: Some(default_config.sample_rate().0 as u64 / 2),
+        ..DecoderOptions::default()
+    };
     let stream = connect_stream(
         &device,
-        &default_config.config(),
-        move |(frame, fps)| {
-            let _ = sender_input.send(AppInput::Update(frame, fps));
+        &default_config,
+        options,
+        move |event| match event {
+            LtcEvent::Frame(decoded) => {
+                let _ = sender_input.send(AppInput::Update(decoded.frame, decoded.fps));
+            }
+            LtcEvent::SignalLost { .. } => {
+                let _ = sender_input.send(AppInput::SignalLost);
+            }
+            _ => {}
         },
         move |err| {
             let _ = sender_error.send(AppInput::Error(err));
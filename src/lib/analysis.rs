@@ -0,0 +1,231 @@
+use std::fmt::Write;
+
+use crate::continuity::Continuity;
+use crate::decoder::DecodedFrame;
+use crate::error::Error;
+use crate::event::LtcEvent;
+use crate::format::TimecodeFormat;
+use crate::source::SourceEvents;
+use crate::userdata::Userdata;
+
+/// A discontinuity in the timecode, see [`LtcEvent::Discontinuity`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Discontinuity {
+    pub position: u64,
+    pub continuity: Continuity,
+}
+
+/// A stretch of the recording without a decodable signal. `end` is `None` if the signal did not
+/// come back before the end of the recording.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dropout {
+    pub start: u64,
+    pub end: Option<u64>,
+}
+
+/// The userdata changed at the frame at `position`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UserdataChange {
+    pub position: u64,
+    pub userdata: Userdata,
+}
+
+/// Summary of the timecode in a whole recording, see [`analyze`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Analysis {
+    pub sample_rate: u32,
+    /// Length of the recording in samples.
+    pub samples: u64,
+    /// Number of frames decoded from the signal.
+    pub frames: u64,
+    pub first: Option<DecodedFrame>,
+    pub last: Option<DecodedFrame>,
+    /// Nominal frame rate detected by the decoder, 0 if none was detected.
+    #[cfg_attr(feature = "serde", serde(with = "crate::decoder::fps"))]
+    pub fps: u128,
+    pub drop: bool,
+    /// Frame rate measured from the sample positions of consecutive frames.
+    pub measured_fps: Option<f64>,
+    /// Measured frame rate relative to the nominal one, `1.0` if the timecode runs at exactly
+    /// its nominal speed. The nominal rate of drop-frame timecode is 1000/1001 of `fps`.
+    pub speed: Option<f64>,
+    pub discontinuities: Vec<Discontinuity>,
+    pub dropouts: Vec<Dropout>,
+    /// Number of frames whose parity does not match, see [`LtcFrame::has_valid_parity`](crate::LtcFrame::has_valid_parity).
+    pub parity_errors: u64,
+    /// Number of biphase mark coding violations in the signal.
+    pub biphase_errors: u64,
+    /// The userdata of the first frame and every change after that.
+    pub userdata: Vec<UserdataChange>,
+}
+
+/// Summarizes what was decoded from a whole recording, with [`decode_source`](crate::decode_source)
+/// or [`decode_source_parallel`](crate::decode_source_parallel). Dropouts are only found if the
+/// events were decoded with a [`signal_timeout`](crate::DecoderOptions::signal_timeout).
+pub fn analyze<E: SourceEvents>(mut events: E) -> Result<Analysis, Error> {
+    let sample_rate = events.sample_rate();
+    let mut analysis = Analysis {
+        sample_rate,
+        samples: 0,
+        frames: 0,
+        first: None,
+        last: None,
+        fps: 0,
+        drop: false,
+        measured_fps: None,
+        speed: None,
+        discontinuities: vec![],
+        dropouts: vec![],
+        parity_errors: 0,
+        biphase_errors: 0,
+        userdata: vec![],
+    };
+    // Samples and frames between frames that directly follow each other within a second, which
+    // works before the frame rate is known.
+    let mut measured = (0u64, 0u64);
+    let mut userdata = None;
+
+    for event in events.by_ref() {
+        match event? {
+            LtcEvent::Frame(frame) if !frame.frame.is_interpolated() => {
+                analysis.frames += 1;
+                if !frame.frame.has_valid_parity() {
+                    analysis.parity_errors += 1;
+                }
+                if let Some(last) = &analysis.last {
+                    let (previous, current) = (last.frame.timecode(), frame.frame.timecode());
+                    if current.frame() == previous.frame() + 1
+                        && (current.hour(), current.minute(), current.second()) == (previous.hour(), previous.minute(), previous.second()) {
+                        measured.0 += frame.position - last.position;
+                        measured.1 += 1;
+                    }
+                }
+                if frame.fps > 0 {
+                    analysis.fps = frame.fps;
+                    analysis.drop = frame.frame.is_drop();
+                }
+                let decoded = frame.frame.decode_userdata(frame.fps);
+                if userdata != Some(frame.frame.userdata().to_vec()) {
+                    userdata = Some(frame.frame.userdata().to_vec());
+                    analysis.userdata.push(UserdataChange { position: frame.position, userdata: decoded });
+                }
+                if analysis.first.is_none() {
+                    analysis.first = Some(frame.clone());
+                }
+                analysis.last = Some(frame);
+            }
+            LtcEvent::Frame(_) => {}
+            LtcEvent::Discontinuity { position, continuity } => {
+                analysis.discontinuities.push(Discontinuity { position, continuity });
+            }
+            LtcEvent::SignalLost { position } => {
+                analysis.dropouts.push(Dropout { start: position, end: None });
+            }
+            LtcEvent::Locked { position } => {
+                if let Some(dropout) = analysis.dropouts.last_mut().filter(|it| it.end.is_none()) {
+                    dropout.end = Some(position);
+                }
+            }
+        }
+    }
+    if measured.0 > 0 {
+        let measured_fps = sample_rate as f64 * measured.1 as f64 / measured.0 as f64;
+        analysis.measured_fps = Some(measured_fps);
+        if analysis.fps > 0 {
+            let nominal = if analysis.drop { analysis.fps as f64 * 1000.0 / 1001.0 } else { analysis.fps as f64 };
+            analysis.speed = Some(measured_fps / nominal);
+        }
+    }
+    analysis.samples = events.position();
+    analysis.biphase_errors = events.biphase_errors();
+    Ok(analysis)
+}
+
+impl Analysis {
+    /// A human readable report, with timecode written in `format`.
+    pub fn report(&self, format: &TimecodeFormat) -> String {
+        let timecode = |frame: &DecodedFrame| format!(
+            "{} at sample {}", format.format(&frame.frame.timecode(), self.fps), frame.position,
+        );
+        let mut report = String::new();
+        let _ = writeln!(report, "length:          {} samples at {} Hz", self.samples, self.sample_rate);
+        let _ = writeln!(report, "frames:          {}", self.frames);
+        match (&self.first, &self.last) {
+            (Some(first), Some(last)) => {
+                let _ = writeln!(report, "first timecode:  {}", timecode(first));
+                let _ = writeln!(report, "last timecode:   {}", timecode(last));
+            }
+            _ => {
+                let _ = writeln!(report, "no timecode found");
+            }
+        }
+        if self.fps > 0 {
+            let _ = writeln!(report, "frame rate:      {} fps{}", self.fps, if self.drop { " drop-frame" } else { "" });
+        } else {
+            let _ = writeln!(report, "frame rate:      unknown");
+        }
+        if let Some(measured_fps) = self.measured_fps {
+            let _ = write!(report, "measured rate:   {:.3} fps", measured_fps);
+            if let Some(speed) = self.speed {
+                let _ = write!(report, ", speed {:.4}", speed);
+            }
+            report.push('\n');
+        }
+        let _ = writeln!(report, "parity errors:   {}", self.parity_errors);
+        let _ = writeln!(report, "biphase errors:  {}", self.biphase_errors);
+        let _ = writeln!(report, "discontinuities: {}", self.discontinuities.len());
+        for discontinuity in &self.discontinuities {
+            let _ = writeln!(report, "  at sample {}: {:?}", discontinuity.position, discontinuity.continuity);
+        }
+        let _ = writeln!(report, "dropouts:        {}", self.dropouts.len());
+        for dropout in &self.dropouts {
+            let _ = match dropout.end {
+                Some(end) => writeln!(report, "  samples {} to {}", dropout.start, end),
+                None => writeln!(report, "  from sample {} to the end", dropout.start),
+            };
+        }
+        let _ = writeln!(report, "userdata:        {} changes", self.userdata.len().saturating_sub(1));
+        for change in &self.userdata {
+            let _ = writeln!(report, "  at sample {}: {:?}", change.position, change.userdata);
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{read_test_file, test_source, Err};
+    use crate::{analyze, decode_source, DecoderOptions, Dropout, Timecode, TimecodeFormat};
+
+    #[test]
+    fn analysis() -> Result<(), Err> {
+        let samples = read_test_file()?;
+        let options = DecoderOptions { signal_timeout: Some(44100), ..DecoderOptions::default() };
+        let analysis = analyze(decode_source(test_source(&samples), options.clone()))?;
+        assert_eq!(analysis.frames, 28);
+        assert_eq!(analysis.first.as_ref().map(|it| it.frame.timecode()), Some(Timecode::new(19, 12, 10, 7, false)));
+        assert_eq!(analysis.last.as_ref().map(|it| it.frame.timecode()), Some(Timecode::new(19, 12, 11, 9, false)));
+        assert_eq!((analysis.fps, analysis.drop), (25, false));
+        assert!((analysis.speed.unwrap() - 1.0).abs() < 0.01, "speed {:?}", analysis.speed);
+        assert_eq!((analysis.parity_errors, analysis.biphase_errors), (0, 0));
+        assert!(analysis.discontinuities.is_empty());
+        assert!(analysis.dropouts.is_empty());
+        assert_eq!(analysis.userdata.len(), 1);
+        assert!(analysis.report(&TimecodeFormat::Smpte).contains("first timecode:  19:12:10:07 at sample 2653\n"));
+
+        // A second of silence in the middle of the recording, in the middle of a frame. Only
+        // that frame is lost, the signal is back with the next one.
+        let mut gap = samples[..44100].to_vec();
+        gap.extend(vec![0.0; 88200]);
+        gap.extend(&samples[44100..]);
+        let analysis = analyze(decode_source(test_source(&gap), options))?;
+        assert_eq!(analysis.frames, 27);
+        assert_eq!(analysis.dropouts, vec![Dropout { start: 37903 + 44100, end: Some(2653 + 12 * 3525 + 88200) }]);
+        assert!((analysis.speed.unwrap() - 1.0).abs() < 0.01, "speed {:?}", analysis.speed);
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use cpal::{BufferSize, FromSample, SampleFormat, SizedSample, Stream, StreamConfig, SupportedStreamConfig};
+use cpal::traits::DeviceTrait;
+
+use crate::decoder::{DecodedFrame, Decoder, DecoderOptions};
+use crate::error::Error;
+use crate::event::LtcEvent;
+use crate::jam_sync::{JamSync, JamSyncEvent};
+
+/// How to open an input stream: a configuration the device supports, the channel to decode LTC
+/// from and the buffer size.
+///
+/// A plain [`SupportedStreamConfig`] converts into the first channel with the default buffer
+/// size.
+#[derive(Clone, Debug)]
+pub struct InputConfig {
+    pub config: SupportedStreamConfig,
+    /// Channel to decode, counting from 0.
+    pub channel: u16,
+    pub buffer_size: BufferSize,
+}
+
+impl InputConfig {
+    /// The configuration to build the stream with.
+    pub fn stream_config(&self) -> StreamConfig {
+        StreamConfig { buffer_size: self.buffer_size, ..self.config.config() }
+    }
+}
+
+impl From<SupportedStreamConfig> for InputConfig {
+    fn from(config: SupportedStreamConfig) -> Self {
+        InputConfig { config, channel: 0, buffer_size: BufferSize::Default }
+    }
+}
+
+impl From<&SupportedStreamConfig> for InputConfig {
+    fn from(config: &SupportedStreamConfig) -> Self {
+        InputConfig::from(config.clone())
+    }
+}
+
+/// Decodes LTC from one channel of an input stream, and reports everything the
+/// [`Decoder`] finds to `callback`. Errors while the stream is running are reported to
+/// `error_callback` as [`Error::Stream`].
+pub fn connect_stream<Callback, CallbackError>(
+    device: &cpal::Device,
+    config: impl Into<InputConfig>,
+    options: DecoderOptions,
+    callback: Callback,
+    error_callback: CallbackError,
+) -> Result<Stream, Error>
+    where Callback: Fn(LtcEvent) + Send + 'static,
+          CallbackError: Fn(Error) + Send + 'static,
+{
+    let mut decoder = Decoder::new(options);
+
+    build_input_stream(
+        device,
+        &config.into(),
+        move |data| {
+            for event in decoder.decode(data) {
+                callback(event);
+            }
+        },
+        error_callback,
+    )
+}
+
+/// Like [`connect_stream`], but runs a [`JamSync`] clock on the decoded frames, which keeps
+/// generating timecode from the input sample rate even when no LTC is coming in.
+pub fn connect_jam_sync<Callback, CallbackError>(
+    device: &cpal::Device,
+    config: impl Into<InputConfig>,
+    options: DecoderOptions,
+    callback: Callback,
+    error_callback: CallbackError,
+) -> Result<Stream, Error>
+    where Callback: Fn(JamSyncEvent) + Send + 'static,
+          CallbackError: Fn(Error) + Send + 'static,
+{
+    let config = config.into();
+    let mut decoder = Decoder::new(options);
+    let mut jam_sync = JamSync::for_config(&config.stream_config());
+
+    build_input_stream(
+        device,
+        &config,
+        move |data| {
+            let frames: Vec<DecodedFrame> = decoder.decode(data).into_iter()
+                .filter_map(LtcEvent::into_frame)
+                .collect();
+            for event in jam_sync.process(&frames, decoder.position()) {
+                callback(event);
+            }
+        },
+        error_callback,
+    )
+}
+
+/// Builds an input stream in the sample format of `config`, and hands the channel to decode of
+/// every block to `callback` as `f32` samples.
+fn build_input_stream<Callback, CallbackError>(
+    device: &cpal::Device,
+    config: &InputConfig,
+    mut callback: Callback,
+    error_callback: CallbackError,
+) -> Result<Stream, Error>
+    where Callback: FnMut(&[f32]) + Send + 'static,
+          CallbackError: Fn(Error) + Send + 'static,
+{
+    let channels = config.config.channels();
+    if config.channel >= channels {
+        return Err(Error::NoChannel { channel: config.channel, channels });
+    }
+    let channel = config.channel as usize;
+    // Reused for every block, so the audio thread only allocates while the blocks grow.
+    let mut samples = vec![];
+    build_interleaved_input_stream(
+        device,
+        config,
+        move |data| {
+            samples.clear();
+            samples.extend(data.iter().skip(channel).step_by(channels as usize));
+            callback(&samples);
+        },
+        error_callback,
+    )
+}
+
+/// Builds an input stream in the sample format of `config`, and hands every block to
+/// `callback` as interleaved `f32` samples of all channels.
+pub(crate) fn build_interleaved_input_stream<Callback, CallbackError>(
+    device: &cpal::Device,
+    config: &InputConfig,
+    callback: Callback,
+    error_callback: CallbackError,
+) -> Result<Stream, Error>
+    where Callback: FnMut(&[f32]) + Send + 'static,
+          CallbackError: Fn(Error) + Send + 'static,
+{
+    match config.config.sample_format() {
+        SampleFormat::I8 => build_typed_input_stream::<i8, _, _>(device, config, callback, error_callback),
+        SampleFormat::I16 => build_typed_input_stream::<i16, _, _>(device, config, callback, error_callback),
+        SampleFormat::I32 => build_typed_input_stream::<i32, _, _>(device, config, callback, error_callback),
+        SampleFormat::U8 => build_typed_input_stream::<u8, _, _>(device, config, callback, error_callback),
+        SampleFormat::U16 => build_typed_input_stream::<u16, _, _>(device, config, callback, error_callback),
+        SampleFormat::U32 => build_typed_input_stream::<u32, _, _>(device, config, callback, error_callback),
+        SampleFormat::F32 => build_typed_input_stream::<f32, _, _>(device, config, callback, error_callback),
+        SampleFormat::F64 => build_typed_input_stream::<f64, _, _>(device, config, callback, error_callback),
+        format => Err(Error::UnsupportedSampleFormat(format)),
+    }
+}
+
+fn build_typed_input_stream<T, Callback, CallbackError>(
+    device: &cpal::Device,
+    config: &InputConfig,
+    mut callback: Callback,
+    error_callback: CallbackError,
+) -> Result<Stream, Error>
+    where T: SizedSample,
+          f32: FromSample<T>,
+          Callback: FnMut(&[f32]) + Send + 'static,
+          CallbackError: Fn(Error) + Send + 'static,
+{
+    let stream = device.build_input_stream(
+        &config.stream_config(),
+        move |data: &[T], _info: &cpal::InputCallbackInfo| {
+            let data: Vec<f32> = data.iter()
+                .map(|&sample| sample.to_sample::<f32>())
+                .collect();
+            callback(&data);
+        },
+        move |err| {
+            error_callback(Error::Stream(err))
+        },
+        None,
+    )?;
+    Ok(stream)
+}
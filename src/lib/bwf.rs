@@ -0,0 +1,367 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::decoder::DecodedFrame;
+use crate::error::Error;
+use crate::wav::{read_chunks, Chunk, BEXT_TIME_REFERENCE, FORMAT_FLOAT};
+
+/// Length of a `bext` chunk without coding history.
+const BEXT_LENGTH: usize = 602;
+const BEXT_VERSION: usize = 346;
+
+/// Where a broadcast wave file sits on the time of day, as written to its `bext` and `iXML`
+/// chunks.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct BwfTimecode {
+    /// Number of samples since midnight at the first sample of the file.
+    pub time_reference: u64,
+    pub sample_rate: u32,
+    /// Nominal frame rate of the timecode, written to `iXML` only.
+    pub fps: u128,
+    pub drop: bool,
+}
+
+impl BwfTimecode {
+    /// Computes the time reference of a recording from frames decoded from it. Frames the
+    /// decoder did not know the frame rate of yet use the rate it detected later, and the
+    /// estimates of all frames are combined by their median so a few misread frames don't
+    /// matter. Returns `None` if there is no frame or no frame rate.
+    ///
+    /// Drop-frame timecode counts real time at 1000/1001 of its nominal frame rate.
+    pub fn from_frames(frames: &[DecodedFrame], sample_rate: u32) -> Option<BwfTimecode> {
+        let frames: Vec<&DecodedFrame> = frames.iter().filter(|it| !it.frame.is_interpolated()).collect();
+        let (fps, drop) = frames.iter().rev()
+            .find(|it| it.fps > 0)
+            .map(|it| (it.fps, it.frame.is_drop()))?;
+        let (num, den) = if drop { (1001, 1000) } else { (1, 1) };
+        let mut estimates: Vec<i128> = frames.iter().map(|frame| {
+            let count = frame.frame.timecode().to_frame_count(fps) as u128;
+            let samples = (count * sample_rate as u128 * num + fps * den / 2) / (fps * den);
+            samples as i128 - frame.position as i128
+        }).collect();
+        estimates.sort_unstable();
+        let day = 86400 * sample_rate as i128;
+        let time_reference = estimates[estimates.len() / 2].rem_euclid(day) as u64;
+        Some(BwfTimecode { time_reference, sample_rate, fps, drop })
+    }
+
+    /// An `iXML` chunk with the timecode rate and time stamp.
+    fn ixml(&self) -> Vec<u8> {
+        let (rate, flag) = if self.drop {
+            (format!("{}/1001", self.fps * 1000), "DF")
+        } else {
+            (format!("{}/1", self.fps), "NDF")
+        };
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <BWFXML>\n\
+             \t<IXML_VERSION>1.61</IXML_VERSION>\n\
+             \t<SPEED>\n\
+             \t\t<TIMECODE_RATE>{}</TIMECODE_RATE>\n\
+             \t\t<TIMECODE_FLAG>{}</TIMECODE_FLAG>\n\
+             \t\t<TIMESTAMP_SAMPLE_RATE>{}</TIMESTAMP_SAMPLE_RATE>\n\
+             \t\t<TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_HI>{}</TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_HI>\n\
+             \t\t<TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_LO>{}</TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_LO>\n\
+             \t</SPEED>\n\
+             </BWFXML>\n",
+            rate, flag, self.sample_rate, self.time_reference >> 32, self.time_reference & 0xFFFF_FFFF,
+        ).into_bytes()
+    }
+}
+
+/// A `bext` chunk, version 1, with only the time reference set.
+fn bext(time_reference: u64) -> Vec<u8> {
+    let mut bext = vec![0u8; BEXT_LENGTH];
+    bext[BEXT_TIME_REFERENCE..BEXT_TIME_REFERENCE + 8].copy_from_slice(&time_reference.to_le_bytes());
+    bext[BEXT_VERSION..BEXT_VERSION + 2].copy_from_slice(&1u16.to_le_bytes());
+    bext
+}
+
+/// What goes into the copy of a chunk.
+enum Content {
+    Copy(Chunk),
+    Replace([u8; 4], Vec<u8>),
+}
+
+/// Copies the WAV file `input` to `output` with the time reference of its `bext` chunk set to
+/// `timecode`, adding a `bext` chunk if there is none. Everything else in the `bext` chunk is
+/// kept. With `ixml`, an `iXML` chunk with the timecode is written as well, replacing any
+/// `iXML` chunk the file had.
+pub fn stamp_bwf<R: Read + Seek, W: Write>(
+    input: &mut R,
+    output: &mut W,
+    timecode: &BwfTimecode,
+    ixml: bool,
+) -> Result<(), Error> {
+    let chunks = read_chunks(input)?;
+    let mut has_bext = chunks.iter().any(|chunk| &chunk.id == b"bext");
+    let mut contents = vec![];
+    for chunk in chunks {
+        match &chunk.id {
+            b"bext" => {
+                let mut bext = vec![0u8; chunk.size as usize];
+                input.seek(SeekFrom::Start(chunk.start))?;
+                input.read_exact(&mut bext)?;
+                if bext.len() < BEXT_LENGTH {
+                    bext.resize(BEXT_LENGTH, 0);
+                }
+                bext[BEXT_TIME_REFERENCE..BEXT_TIME_REFERENCE + 8].copy_from_slice(&timecode.time_reference.to_le_bytes());
+                contents.push(Content::Replace(*b"bext", bext));
+            }
+            b"iXML" if ixml => {}
+            b"fmt " => {
+                contents.push(Content::Copy(chunk));
+                if !has_bext {
+                    contents.push(Content::Replace(*b"bext", bext(timecode.time_reference)));
+                    has_bext = true;
+                }
+            }
+            _ => contents.push(Content::Copy(chunk)),
+        }
+    }
+    if ixml {
+        let data = contents.iter().position(|it| matches!(it, Content::Copy(chunk) if &chunk.id == b"data"));
+        contents.insert(data.unwrap_or(contents.len()), Content::Replace(*b"iXML", timecode.ixml()));
+    }
+
+    let size = |content: &Content| match content {
+        Content::Copy(chunk) => chunk.size,
+        Content::Replace(_, data) => data.len() as u64,
+    };
+    let riff_size: u64 = 4 + contents.iter().map(|it| 8 + size(it) + size(it) % 2).sum::<u64>();
+    let riff_size = u32::try_from(riff_size)
+        .map_err(|_| Error::InvalidFile("larger than 4 GB".to_string()))?;
+    output.write_all(b"RIFF")?;
+    output.write_all(&riff_size.to_le_bytes())?;
+    output.write_all(b"WAVE")?;
+    for content in &contents {
+        let length = size(content);
+        match content {
+            Content::Copy(chunk) => {
+                output.write_all(&chunk.id)?;
+                output.write_all(&(length as u32).to_le_bytes())?;
+                input.seek(SeekFrom::Start(chunk.start))?;
+                let copied = io::copy(&mut input.by_ref().take(length), output)?;
+                if copied < length {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+                }
+            }
+            Content::Replace(id, data) => {
+                output.write_all(id)?;
+                output.write_all(&(length as u32).to_le_bytes())?;
+                output.write_all(data)?;
+            }
+        }
+        if length % 2 == 1 {
+            output.write_all(&[0])?;
+        }
+    }
+    output.flush()?;
+    Ok(())
+}
+
+/// Writes a broadcast wave file with 32 bit float samples, like a recording as it comes in.
+///
+/// The header is kept up to date every second of audio, so the file can be read even if the
+/// writer is never [finished](Self::finish). The time reference can be set at any time, see
+/// [`set_time_reference`](Self::set_time_reference). Like every WAV file, the file cannot hold
+/// more than 4 GB.
+pub struct BwfWriter<W: Write + Seek> {
+    writer: W,
+    channels: u16,
+    sample_rate: u32,
+    /// Offset of the `bext` chunk contents.
+    bext_start: u64,
+    /// Offset of the `fact` chunk contents.
+    fact_start: u64,
+    data_start: u64,
+    /// Number of sample frames written.
+    length: u64,
+    /// Number of sample frames written since the header was last updated.
+    unsaved: u64,
+}
+
+impl BwfWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(path: P, channels: u16, sample_rate: u32) -> Result<Self, Error> {
+        BwfWriter::new(BufWriter::new(File::create(path)?), channels, sample_rate)
+    }
+}
+
+impl<W: Write + Seek> BwfWriter<W> {
+    /// Writes the headers of a file with `channels` interleaved channels to `writer`.
+    pub fn new(mut writer: W, channels: u16, sample_rate: u32) -> Result<Self, Error> {
+        let mut fmt = vec![];
+        fmt.extend(FORMAT_FLOAT.to_le_bytes());
+        fmt.extend(channels.to_le_bytes());
+        fmt.extend(sample_rate.to_le_bytes());
+        fmt.extend((sample_rate * channels as u32 * 4).to_le_bytes());
+        fmt.extend((channels * 4).to_le_bytes());
+        fmt.extend(32u16.to_le_bytes());
+        fmt.extend(0u16.to_le_bytes());
+
+        writer.write_all(b"RIFF\0\0\0\0WAVE")?;
+        let mut start = 12;
+        let mut offsets = vec![];
+        for (id, chunk) in [(b"fmt ", fmt), (b"fact", vec![0; 4]), (b"bext", bext(0)), (b"data", vec![])] {
+            writer.write_all(id)?;
+            writer.write_all(&(chunk.len() as u32).to_le_bytes())?;
+            writer.write_all(&chunk)?;
+            offsets.push(start + 8);
+            start += 8 + chunk.len() as u64;
+        }
+        let mut bwf = BwfWriter {
+            writer,
+            channels,
+            sample_rate,
+            fact_start: offsets[1],
+            bext_start: offsets[2],
+            data_start: offsets[3],
+            length: 0,
+            unsaved: 0,
+        };
+        bwf.update_header()?;
+        Ok(bwf)
+    }
+
+    /// Number of sample frames written so far.
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Appends interleaved samples, `samples` has to hold whole sample frames.
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), Error> {
+        let frames = (samples.len() / self.channels.max(1) as usize) as u64;
+        let size = (self.length + frames) * self.channels as u64 * 4;
+        if self.data_start + size > u32::MAX as u64 {
+            return Err(Error::InvalidFile("recording larger than 4 GB".to_string()));
+        }
+        let bytes: Vec<u8> = samples.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        self.writer.write_all(&bytes)?;
+        self.length += frames;
+        self.unsaved += frames;
+        if self.unsaved >= self.sample_rate as u64 {
+            self.update_header()?;
+        }
+        Ok(())
+    }
+
+    /// Sets the number of samples since midnight at the first sample of the file.
+    pub fn set_time_reference(&mut self, time_reference: u64) -> Result<(), Error> {
+        self.writer.seek(SeekFrom::Start(self.bext_start + BEXT_TIME_REFERENCE as u64))?;
+        self.writer.write_all(&time_reference.to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+
+    /// Updates the header and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W, Error> {
+        self.update_header()?;
+        Ok(self.writer)
+    }
+
+    fn update_header(&mut self) -> Result<(), Error> {
+        let size = self.length * self.channels as u64 * 4;
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer.write_all(&((self.data_start + size - 8) as u32).to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(self.fact_start))?;
+        self.writer.write_all(&(self.length as u32).to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(self.data_start - 4))?;
+        self.writer.write_all(&(size as u32).to_le_bytes())?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+        self.unsaved = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::tests::{read_test_file, Err};
+    use crate::wav::tests::wav_file;
+    use crate::wav::{read_chunks, BEXT_TIME_REFERENCE, FORMAT_PCM};
+    use crate::{decode_source, stamp_bwf, BwfTimecode, BwfWriter, DecoderOptions, LtcEvent, SampleSource, WavReader};
+
+    use super::bext;
+
+    #[test]
+    fn stamp() -> Result<(), Err> {
+        let samples = read_test_file()?;
+        for time_reference in [None, Some(1234)] {
+            let mut input = wav_file(FORMAT_PCM, 16, 88200, &samples, time_reference);
+            let bext_chunks = |file: &[u8]| -> Result<Vec<Vec<u8>>, Err> {
+                Ok(read_chunks(&mut Cursor::new(file))?.iter()
+                    .filter(|chunk| &chunk.id == b"bext")
+                    .map(|chunk| file[chunk.start as usize..(chunk.start + chunk.size) as usize].to_vec())
+                    .collect())
+            };
+            if let Some(chunk) = read_chunks(&mut Cursor::new(&input))?.iter().find(|chunk| &chunk.id == b"bext") {
+                let description = chunk.start as usize;
+                input[description..description + 11].copy_from_slice(b"Description");
+            }
+            let mut reader = WavReader::new(Cursor::new(&input))?;
+            reader.set_channel(1)?;
+            let frames = decode_source(reader, DecoderOptions::default())
+                .filter_map(|event| event.map(LtcEvent::into_frame).transpose())
+                .collect::<Result<Vec<_>, _>>()?;
+            let timecode = BwfTimecode::from_frames(&frames, 88200).ok_or("no timecode")?;
+            // 19:12:10:07 at 25 fps starts at sample 2653 of the file, the positions of single
+            // frames jitter by a few dozen samples.
+            assert!(timecode.time_reference.abs_diff(1728257 * 88200 / 25 - 2653) < 100);
+            assert_eq!((timecode.fps, timecode.drop), (25, false));
+
+            let mut output = vec![];
+            stamp_bwf(&mut Cursor::new(&input), &mut output, &timecode, true)?;
+            let text = String::from_utf8_lossy(&output);
+            assert!(text.contains("<TIMECODE_RATE>25/1</TIMECODE_RATE>"));
+            assert!(text.contains(&format!("<TIMESTAMP_SAMPLES_SINCE_MIDNIGHT_LO>{}<", timecode.time_reference & 0xFFFF_FFFF)));
+            let original = bext_chunks(&input)?;
+            let stamped = bext_chunks(&output)?;
+            assert_eq!(stamped.len(), 1);
+            let expected = original.first().cloned().unwrap_or_else(|| bext(0));
+            assert_eq!(stamped[0].len(), expected.len());
+            for (index, (&byte, &expected)) in stamped[0].iter().zip(&expected).enumerate() {
+                if !(BEXT_TIME_REFERENCE..BEXT_TIME_REFERENCE + 8).contains(&index) {
+                    assert_eq!(byte, expected, "bext byte {}", index);
+                }
+            }
+            let stamped = WavReader::new(Cursor::new(&output))?;
+            assert_eq!(stamped.time_reference(), Some(timecode.time_reference));
+            assert_eq!(stamped.len(), samples.len() as u64);
+            let data = stamped.data_start() as usize;
+            assert_eq!(&output[data..], &input[input.len() - (output.len() - data)..]);
+        }
+        assert_eq!(BwfTimecode::from_frames(&[], 48000), None);
+        Ok(())
+    }
+
+    #[test]
+    fn write() -> Result<(), Err> {
+        let samples: Vec<f32> = (0..48000 * 3).map(|it| (it % 100) as f32 / 100.0).collect();
+        let mut writer = BwfWriter::new(Cursor::new(vec![]), 2, 48000)?;
+        for block in samples.chunks(1000) {
+            writer.write(block)?;
+        }
+        // Readable before it was finished, up to the last header update.
+        let file = writer.writer.get_ref().clone();
+        assert_eq!(WavReader::new(Cursor::new(&file))?.len(), 48000);
+        writer.set_time_reference(1234)?;
+        assert_eq!(writer.len(), 72000);
+        let file = writer.finish()?.into_inner();
+
+        let mut reader = WavReader::new(Cursor::new(&file))?;
+        assert_eq!((reader.format().channels, reader.format().sample_rate, reader.format().float), (2, 48000, true));
+        assert_eq!((reader.len(), reader.time_reference()), (72000, Some(1234)));
+        reader.set_channel(1)?;
+        let mut channel = vec![];
+        while reader.read(&mut channel, 48000)? > 0 {}
+        assert_eq!(channel, samples.iter().skip(1).step_by(2).copied().collect::<Vec<_>>());
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+use cpal::Stream;
+use cpal::traits::StreamTrait;
+
+use crate::audio::InputConfig;
+use crate::decoder::DecoderOptions;
+use crate::error::Error;
+use crate::event::LtcEvent;
+
+/// A running input stream whose events are delivered through a channel instead of a callback,
+/// see [`connect_channel`].
+///
+/// The audio thread never waits for the consumer: when `capacity` events are pending, new
+/// events are dropped and counted in [`dropped`](Self::dropped). Once there is room again, an
+/// [`Error::Lagged`] with the number of events lost is received before the next event. Errors
+/// of the running stream are never dropped, and do not count towards the capacity.
+pub struct EventReceiver {
+    stream: Stream,
+    receiver: mpsc::Receiver<Result<LtcEvent, Error>>,
+    /// Number of events sent but not received yet.
+    pending: Arc<AtomicUsize>,
+    dropped: Arc<AtomicU64>,
+}
+
+/// The sending side of the channel, shared by the data and error callbacks of the stream.
+struct ChannelSender {
+    sender: mpsc::Sender<Result<LtcEvent, Error>>,
+    capacity: usize,
+    pending: Arc<AtomicUsize>,
+    dropped: Arc<AtomicU64>,
+    /// Events dropped since the last one that was sent.
+    lagged: AtomicU64,
+}
+
+impl ChannelSender {
+    fn send_event(&self, event: LtcEvent) {
+        if self.pending.load(Ordering::Acquire) >= self.capacity {
+            self.lagged.fetch_add(1, Ordering::Relaxed);
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        let lagged = self.lagged.swap(0, Ordering::Relaxed);
+        if lagged > 0 {
+            let _ = self.sender.send(Err(Error::Lagged(lagged)));
+        }
+        self.pending.fetch_add(1, Ordering::Release);
+        let _ = self.sender.send(Ok(event));
+    }
+
+    fn send_error(&self, err: Error) {
+        let _ = self.sender.send(Err(err));
+    }
+}
+
+/// Like [`connect_stream`](crate::connect_stream), but returns a handle from which up to
+/// `capacity` pending events can be received on any thread.
+pub fn connect_channel(
+    device: &cpal::Device,
+    config: impl Into<InputConfig>,
+    options: DecoderOptions,
+    capacity: usize,
+) -> Result<EventReceiver, Error> {
+    let (sender, receiver) = mpsc::channel();
+    let pending = Arc::new(AtomicUsize::new(0));
+    let dropped = Arc::new(AtomicU64::new(0));
+    let sender = Arc::new(ChannelSender {
+        sender,
+        capacity,
+        pending: pending.clone(),
+        dropped: dropped.clone(),
+        lagged: AtomicU64::new(0),
+    });
+    let error_sender = sender.clone();
+    let stream = crate::connect_stream(
+        device,
+        config,
+        options,
+        move |event| sender.send_event(event),
+        move |err| error_sender.send_error(err),
+    )?;
+    Ok(EventReceiver { stream, receiver, pending, dropped })
+}
+
+impl EventReceiver {
+    /// Starts or resumes the input stream.
+    pub fn play(&self) -> Result<(), Error> {
+        Ok(self.stream.play()?)
+    }
+
+    /// Pauses the input stream, events that are already queued can still be received.
+    pub fn pause(&self) -> Result<(), Error> {
+        Ok(self.stream.pause()?)
+    }
+
+    /// Waits for the next event or stream error.
+    pub fn recv(&self) -> Option<Result<LtcEvent, Error>> {
+        self.received(self.receiver.recv().ok())
+    }
+
+    /// Returns the next event or stream error, if one is pending.
+    pub fn try_recv(&self) -> Option<Result<LtcEvent, Error>> {
+        self.received(self.receiver.try_recv().ok())
+    }
+
+    /// A blocking iterator over all events and stream errors.
+    pub fn iter(&self) -> EventIter<'_> {
+        EventIter { receiver: self }
+    }
+
+    /// Number of events dropped so far because the channel was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Makes room for another event once one was received.
+    fn received(&self, item: Option<Result<LtcEvent, Error>>) -> Option<Result<LtcEvent, Error>> {
+        if let Some(Ok(_)) = item {
+            self.pending.fetch_sub(1, Ordering::AcqRel);
+        }
+        item
+    }
+}
+
+/// A blocking iterator over the events and stream errors of an [`EventReceiver`].
+pub struct EventIter<'a> {
+    receiver: &'a EventReceiver,
+}
+
+impl Iterator for EventIter<'_> {
+    type Item = Result<LtcEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv()
+    }
+}
+
+impl<'a> IntoIterator for &'a EventReceiver {
+    type Item = Result<LtcEvent, Error>;
+    type IntoIter = EventIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use std::sync::mpsc;
+
+    use crate::{Error, LtcEvent};
+
+    use super::ChannelSender;
+
+    #[test]
+    fn report_dropped() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = ChannelSender {
+            sender,
+            capacity: 2,
+            pending: Arc::new(AtomicUsize::new(0)),
+            dropped: Arc::new(AtomicU64::new(0)),
+            lagged: AtomicU64::new(0),
+        };
+        for position in 0..5 {
+            sender.send_event(LtcEvent::Locked { position });
+        }
+        sender.send_error(Error::NoDevice);
+        assert!(matches!(receiver.try_recv(), Ok(Ok(LtcEvent::Locked { position: 0 }))));
+        assert!(matches!(receiver.try_recv(), Ok(Ok(LtcEvent::Locked { position: 1 }))));
+        assert!(matches!(receiver.try_recv(), Ok(Err(Error::NoDevice))));
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(sender.dropped.load(Ordering::Relaxed), 3);
+
+        sender.pending.fetch_sub(2, Ordering::AcqRel);
+        sender.send_event(LtcEvent::SignalLost { position: 5 });
+        assert!(matches!(receiver.try_recv(), Ok(Err(Error::Lagged(3)))));
+        assert!(matches!(receiver.try_recv(), Ok(Ok(LtcEvent::SignalLost { position: 5 }))));
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use crate::decoder::DecodedFrame;
+use crate::timecode::Timecode;
+
+/// How a frame relates to the one before it.
+///
+/// Serialized in snake case, as a plain string for the variants without data and as an object
+/// with a single key otherwise, e.g. `"repeated"`, `{"skipped": 2}` or
+/// `{"rate_changed": {"from": 25, "to": 30}}`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Continuity {
+    /// The frame directly follows the previous one.
+    Continuous,
+    /// The frame has the same timecode as the previous one.
+    Repeated,
+    /// This many frames were lost between the previous frame and this one, but the timecode
+    /// still matches the time that passed in between.
+    Skipped(u64),
+    /// The timecode jumped by this many frames relative to the time that passed since the
+    /// previous frame. Negative values are jumps backward.
+    Jumped(i64),
+    /// The measured frame rate changed.
+    RateChanged {
+        #[cfg_attr(feature = "serde", serde(with = "crate::decoder::fps"))]
+        from: u128,
+        #[cfg_attr(feature = "serde", serde(with = "crate::decoder::fps"))]
+        to: u128,
+    },
+    /// The frame carries the drop flag, but has a frame number that drop-frame timecode skips.
+    DropFrameViolation,
+}
+
+/// Classifies every frame relative to the previous one.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ContinuityDetector {
+    previous: Option<DecodedFrame>,
+    period: Option<u64>,
+}
+
+impl ContinuityDetector {
+    pub fn new() -> ContinuityDetector {
+        ContinuityDetector {
+            previous: None,
+            period: None,
+        }
+    }
+
+    /// Classifies `frame` and remembers it for the next one. If several classifications apply,
+    /// drop-frame violations take precedence over rate changes, which take precedence over the
+    /// timecode sequence. The first frame is always continuous.
+    pub fn classify(&mut self, frame: &DecodedFrame) -> Continuity {
+        let previous = match self.previous.replace(frame.clone()) {
+            Some(previous) => previous,
+            None => return Continuity::Continuous,
+        };
+        if violates_drop_frame(frame) {
+            return Continuity::DropFrameViolation;
+        }
+        if previous.fps > 0 && frame.fps > 0 && previous.fps != frame.fps {
+            return Continuity::RateChanged { from: previous.fps, to: frame.fps };
+        }
+        let fps = if frame.fps > 0 { frame.fps } else { previous.fps };
+        if fps == 0 {
+            return Continuity::Continuous;
+        }
+
+        let elapsed = frame.position.saturating_sub(previous.position);
+        let day = Timecode::frames_per_day(fps, frame.frame.is_drop()) as i64;
+        let mut frames = frame.frame.timecode().to_frame_count(fps) as i64
+            - previous.frame.timecode().to_frame_count(fps) as i64;
+        if frames > day / 2 {
+            frames -= day;
+        } else if frames < -day / 2 {
+            frames += day;
+        }
+        let expected = match self.period {
+            Some(period) => ((elapsed + period / 2) / period) as i64,
+            None if frames > 1 && frames <= fps as i64 => frames,
+            None => 1,
+        };
+        if frames == 1 && elapsed > 0 {
+            self.period = Some(elapsed);
+        }
+
+        match frames {
+            0 => Continuity::Repeated,
+            1 => Continuity::Continuous,
+            frames if frames == expected => Continuity::Skipped(frames as u64 - 1),
+            frames => Continuity::Jumped(frames - expected),
+        }
+    }
+}
+
+fn violates_drop_frame(frame: &DecodedFrame) -> bool {
+    let timecode = frame.frame.timecode();
+    timecode.is_drop()
+        && frame.fps > 0
+        && frame.fps.is_multiple_of(30)
+        && timecode.second() == 0
+        && !timecode.minute().is_multiple_of(10)
+        && (timecode.frame() as u128) < frame.fps / 15
+}
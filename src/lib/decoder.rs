@@ -0,0 +1,175 @@
+use crate::continuity::{Continuity, ContinuityDetector};
+use crate::event::LtcEvent;
+use crate::filter;
+use crate::freewheel::Freewheel;
+use crate::ltc_frame::LtcFrame;
+use crate::reader::LtcFrameReader;
+
+/// Settings for a [`Decoder`].
+#[derive(Clone, Debug, Default)]
+pub struct DecoderOptions {
+    /// Number of frames to generate from the last good frame when the signal drops out.
+    /// `0` disables freewheeling.
+    pub freewheel_frames: u32,
+    /// Number of samples without a decoded frame after which the signal counts as lost.
+    /// `None` never reports the signal as lost.
+    pub signal_timeout: Option<u64>,
+}
+
+/// A frame as returned by the [`Decoder`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecodedFrame {
+    pub frame: LtcFrame,
+    /// Measured frame rate, or 0 if it is not known yet.
+    #[cfg_attr(feature = "serde", serde(with = "fps"))]
+    pub fps: u128,
+    /// Sample at which the frame started, counted from the start of the input.
+    pub position: u64,
+}
+
+/// Frame rates are `u128` for consistency with [`LtcFrameReader::frame_rate`], but serialized as
+/// `u64`, which more formats support.
+#[cfg(feature = "serde")]
+pub(crate) mod fps {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(fps: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(*fps as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+        u64::deserialize(deserializer).map(u128::from)
+    }
+}
+
+/// The full filter chain from audio samples to LTC frames, keeping track of sample positions.
+#[derive(Clone)]
+pub struct Decoder {
+    filter_lowpass: filter::RawLowpassFilter,
+    filter_denoise: filter::RawDenoiseFilter,
+    filter_demod: filter::RawDemodFilter,
+    filter_fm_denoise: filter::FmDenoiseFilter,
+    filter_fm_decode: filter::FmDecodeFilter,
+    frame_reader: LtcFrameReader,
+    freewheel: Freewheel,
+    continuity: ContinuityDetector,
+    signal_timeout: Option<u64>,
+    last_signal: Option<u64>,
+    locked: bool,
+    position: u64,
+}
+
+impl Decoder {
+    pub fn new(options: DecoderOptions) -> Decoder {
+        Decoder {
+            filter_lowpass: filter::RawLowpassFilter::new(),
+            filter_denoise: filter::RawDenoiseFilter::new(),
+            filter_demod: filter::RawDemodFilter::new(),
+            filter_fm_denoise: filter::FmDenoiseFilter::new(),
+            filter_fm_decode: filter::FmDecodeFilter::new(),
+            frame_reader: LtcFrameReader::new(),
+            freewheel: Freewheel::new(options.freewheel_frames),
+            continuity: ContinuityDetector::new(),
+            signal_timeout: options.signal_timeout,
+            last_signal: None,
+            locked: false,
+            position: 0,
+        }
+    }
+
+    /// Like [`new`](Self::new), but counts sample positions from `position`, for decoding a
+    /// stream from the middle.
+    pub(crate) fn starting_at(options: DecoderOptions, position: u64) -> Decoder {
+        Decoder { position, ..Decoder::new(options) }
+    }
+
+    /// Whether both decoders will return the same events for the same samples from here on.
+    /// Only the error counts may differ.
+    pub(crate) fn same_state(&self, other: &Decoder) -> bool {
+        self.filter_lowpass == other.filter_lowpass
+            && self.filter_denoise == other.filter_denoise
+            && self.filter_demod == other.filter_demod
+            && self.filter_fm_denoise == other.filter_fm_denoise
+            && self.filter_fm_decode.same_state(&other.filter_fm_decode)
+            && self.frame_reader == other.frame_reader
+            && self.freewheel == other.freewheel
+            && self.continuity == other.continuity
+            && self.signal_timeout == other.signal_timeout
+            && self.last_signal == other.last_signal
+            && self.locked == other.locked
+            && self.position == other.position
+    }
+
+    /// Number of samples decoded so far.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub fn frame_rate(&self) -> Option<u128> {
+        self.frame_reader.frame_rate()
+    }
+
+    /// Number of biphase mark coding violations in the signal so far.
+    pub fn biphase_errors(&self) -> u64 {
+        self.filter_fm_decode.errors()
+    }
+
+    /// Decodes the next block of mono samples.
+    pub fn decode(&mut self, data: &[f32]) -> Vec<LtcEvent> {
+        let start = self.position;
+        self.position += data.len() as u64;
+
+        let data = data.to_vec();
+        let data = self.filter_lowpass.filter(&data);
+        let data = self.filter_denoise.filter(&data);
+        let runs = self.filter_demod.filter_indexed(&data);
+        let values: Vec<i64> = runs.iter().map(|&(_, value)| value).collect();
+        let cells = self.filter_fm_denoise.filter_indexed(&values);
+        let values: Vec<i64> = cells.iter().map(|&(_, value)| value).collect();
+        let bits: Vec<(u64, bool)> = self.filter_fm_decode.filter_indexed(&values).into_iter()
+            .map(|(index, bit)| (start + runs[cells[index].0].0 as u64, bit))
+            .collect();
+        let decoded = self.frame_reader.read_positioned(&bits);
+        let fps = self.frame_reader.frame_rate()
+            .unwrap_or(0);
+
+        let mut frames = vec![];
+        for (position, frame) in decoded {
+            let frame = DecodedFrame { frame, fps, position };
+            frames.extend(self.freewheel.fill_before(&frame));
+            self.freewheel.lock(&frame);
+            frames.push(frame);
+        }
+        frames.extend(self.freewheel.fill(self.position));
+
+        let mut result = vec![];
+        for frame in frames {
+            let position = frame.position;
+            self.check_signal(position, &mut result);
+            if !frame.frame.is_interpolated() {
+                if !self.locked {
+                    self.locked = true;
+                    result.push(LtcEvent::Locked { position });
+                }
+                self.last_signal = Some(position);
+            }
+            let continuity = self.continuity.classify(&frame);
+            result.push(LtcEvent::Frame(frame));
+            if continuity != Continuity::Continuous {
+                result.push(LtcEvent::Discontinuity { position, continuity });
+            }
+        }
+        self.check_signal(self.position, &mut result);
+        result
+    }
+
+    fn check_signal(&mut self, position: u64, result: &mut Vec<LtcEvent>) {
+        if let (true, Some(timeout), Some(last_signal)) = (self.locked, self.signal_timeout, self.last_signal) {
+            if position > last_signal + timeout {
+                self.locked = false;
+                result.push(LtcEvent::SignalLost { position: last_signal + timeout });
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt::Write;
+
+use crate::timecode::Timecode;
+
+/// One event of a CMX3600 edit decision list: a cut of `source_in` to `source_out` of `reel`,
+/// placed at `record_in` to `record_out`.
+pub(crate) struct EdlEvent<'a> {
+    pub reel: &'a str,
+    /// `V`, `A`, `A2`, `AA`, `AA/V` and so on.
+    pub track: &'a str,
+    pub source_in: Timecode,
+    pub source_out: Timecode,
+    pub record_in: Timecode,
+    pub record_out: Timecode,
+    /// Written as a `FROM CLIP NAME` comment.
+    pub clip_name: Option<&'a str>,
+    /// Further comment lines.
+    pub comments: Vec<String>,
+}
+
+/// Writes `events` as a CMX3600 edit decision list. Reel names are cut to 8 characters, and
+/// event numbers wrap after 999 like in the original format.
+pub(crate) fn write_edl(title: &str, drop: bool, events: &[EdlEvent]) -> String {
+    let mut edl = String::new();
+    let _ = writeln!(edl, "TITLE: {}", title);
+    let _ = writeln!(edl, "FCM: {}", if drop { "DROP FRAME" } else { "NON-DROP FRAME" });
+    for (index, event) in events.iter().enumerate() {
+        let reel: String = event.reel.chars()
+            .map(|char| if char.is_ascii_alphanumeric() || char == '_' { char.to_ascii_uppercase() } else { '_' })
+            .take(8)
+            .collect();
+        let _ = writeln!(edl);
+        let _ = writeln!(
+            edl, "{:03}  {:<8} {:<5} C        {} {} {} {}",
+            index % 999 + 1, reel, event.track,
+            event.source_in, event.source_out, event.record_in, event.record_out,
+        );
+        if let Some(clip_name) = event.clip_name {
+            let _ = writeln!(edl, "* FROM CLIP NAME: {}", clip_name);
+        }
+        for comment in &event.comments {
+            let _ = writeln!(edl, "* {}", comment);
+        }
+    }
+    edl
+}
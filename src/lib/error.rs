@@ -0,0 +1,174 @@
+use std::fmt::Formatter;
+
+/// Everything that can go wrong while setting up or running an input stream, or reading a
+/// file.
+#[derive(Debug)]
+pub enum Error {
+    /// There is no input device to use.
+    NoDevice,
+    /// The input devices could not be listed.
+    #[cfg(feature = "audio")]
+    Devices(cpal::DevicesError),
+    /// The input device has no usable configuration.
+    #[cfg(feature = "audio")]
+    Config(cpal::DefaultStreamConfigError),
+    /// The configurations supported by the input device could not be listed.
+    #[cfg(feature = "audio")]
+    SupportedConfigs(cpal::SupportedStreamConfigsError),
+    /// The input device delivers samples in a format the decoder cannot read.
+    #[cfg(feature = "audio")]
+    UnsupportedSampleFormat(cpal::SampleFormat),
+    /// The input device does not support this sample rate.
+    #[cfg(feature = "audio")]
+    UnsupportedSampleRate(u32),
+    /// The input stream could not be built.
+    #[cfg(feature = "audio")]
+    BuildStream(cpal::BuildStreamError),
+    /// The input stream could not be started.
+    #[cfg(feature = "audio")]
+    PlayStream(cpal::PlayStreamError),
+    /// The input stream could not be paused.
+    #[cfg(feature = "audio")]
+    PauseStream(cpal::PauseStreamError),
+    /// The running input stream failed.
+    #[cfg(feature = "audio")]
+    Stream(cpal::StreamError),
+    /// The consumer fell behind and this many events were dropped.
+    Lagged(u64),
+    /// The input has no audio track with this index.
+    NoTrack { track: usize, tracks: usize },
+    /// The input has no channel with this index.
+    NoChannel { channel: u16, channels: u16 },
+    /// An input file could not be read.
+    Io(std::io::Error),
+    /// An input file is damaged or in a format that is not supported.
+    InvalidFile(String),
+    /// No timecode could be decoded from the input.
+    NoTimecode,
+    /// A media file could not be decoded.
+    #[cfg(feature = "media")]
+    Media(symphonia::core::errors::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NoDevice => write!(f, "no input device available"),
+            #[cfg(feature = "audio")]
+            Error::Devices(err) => write!(f, "could not list input devices: {}", err),
+            #[cfg(feature = "audio")]
+            Error::Config(err) => write!(f, "no supported input config: {}", err),
+            #[cfg(feature = "audio")]
+            Error::SupportedConfigs(err) => write!(f, "could not list supported input configs: {}", err),
+            #[cfg(feature = "audio")]
+            Error::UnsupportedSampleFormat(format) => write!(f, "unsupported sample format {}", format),
+            #[cfg(feature = "audio")]
+            Error::UnsupportedSampleRate(rate) => write!(f, "the input device does not support {} Hz", rate),
+            #[cfg(feature = "audio")]
+            Error::BuildStream(err) => write!(f, "could not build input stream: {}", err),
+            #[cfg(feature = "audio")]
+            Error::PlayStream(err) => write!(f, "could not start input stream: {}", err),
+            #[cfg(feature = "audio")]
+            Error::PauseStream(err) => write!(f, "could not pause input stream: {}", err),
+            #[cfg(feature = "audio")]
+            Error::Stream(err) => write!(f, "error in input stream: {}", err),
+            Error::Lagged(count) => write!(f, "{} events dropped", count),
+            Error::NoTrack { track, tracks } =>
+                write!(f, "no audio track {}, the input has {} audio tracks counting from 0", track, tracks),
+            Error::NoChannel { channel, channels } =>
+                write!(f, "no channel {}, the input has {} channels counting from 0", channel, channels),
+            Error::Io(err) => write!(f, "could not read input: {}", err),
+            Error::InvalidFile(reason) => write!(f, "invalid input file: {}", reason),
+            Error::NoTimecode => write!(f, "no timecode found in the input"),
+            #[cfg(feature = "media")]
+            Error::Media(err) => write!(f, "could not decode input: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "audio")]
+            Error::Devices(err) => Some(err),
+            #[cfg(feature = "audio")]
+            Error::Config(err) => Some(err),
+            #[cfg(feature = "audio")]
+            Error::SupportedConfigs(err) => Some(err),
+            #[cfg(feature = "audio")]
+            Error::BuildStream(err) => Some(err),
+            #[cfg(feature = "audio")]
+            Error::PlayStream(err) => Some(err),
+            #[cfg(feature = "audio")]
+            Error::PauseStream(err) => Some(err),
+            #[cfg(feature = "audio")]
+            Error::Stream(err) => Some(err),
+            Error::Io(err) => Some(err),
+            #[cfg(feature = "media")]
+            Error::Media(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<cpal::DevicesError> for Error {
+    fn from(err: cpal::DevicesError) -> Self {
+        Error::Devices(err)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<cpal::DefaultStreamConfigError> for Error {
+    fn from(err: cpal::DefaultStreamConfigError) -> Self {
+        Error::Config(err)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<cpal::SupportedStreamConfigsError> for Error {
+    fn from(err: cpal::SupportedStreamConfigsError) -> Self {
+        Error::SupportedConfigs(err)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<cpal::BuildStreamError> for Error {
+    fn from(err: cpal::BuildStreamError) -> Self {
+        Error::BuildStream(err)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<cpal::PlayStreamError> for Error {
+    fn from(err: cpal::PlayStreamError) -> Self {
+        Error::PlayStream(err)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<cpal::PauseStreamError> for Error {
+    fn from(err: cpal::PauseStreamError) -> Self {
+        Error::PauseStream(err)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<cpal::StreamError> for Error {
+    fn from(err: cpal::StreamError) -> Self {
+        Error::Stream(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+#[cfg(feature = "media")]
+impl From<symphonia::core::errors::Error> for Error {
+    fn from(err: symphonia::core::errors::Error) -> Self {
+        Error::Media(err)
+    }
+}
@@ -0,0 +1,40 @@
+use crate::continuity::Continuity;
+use crate::decoder::DecodedFrame;
+
+/// Everything the [`Decoder`](crate::Decoder) reports while decoding.
+///
+/// Serialized as an object with a snake case `type` key next to the fields of the variant, e.g.
+/// `{"type": "frame", "frame": {...}, "fps": 25, "position": 2653}` or
+/// `{"type": "signal_lost", "position": 107828}`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum LtcEvent {
+    /// A decoded or interpolated frame.
+    Frame(DecodedFrame),
+    /// The frame at `position` did not continue the timecode of the previous frame. Reported
+    /// right after that frame.
+    Discontinuity { position: u64, continuity: Continuity },
+    /// Frames are being decoded from the signal, starting with the frame at `position`.
+    /// Reported right before that frame.
+    Locked { position: u64 },
+    /// No frame was decoded for longer than the
+    /// [signal timeout](crate::DecoderOptions::signal_timeout), since sample `position`.
+    SignalLost { position: u64 },
+}
+
+impl LtcEvent {
+    pub fn frame(&self) -> Option<&DecodedFrame> {
+        match self {
+            LtcEvent::Frame(frame) => Some(frame),
+            _ => None,
+        }
+    }
+
+    pub fn into_frame(self) -> Option<DecodedFrame> {
+        match self {
+            LtcEvent::Frame(frame) => Some(frame),
+            _ => None,
+        }
+    }
+}
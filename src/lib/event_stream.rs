@@ -0,0 +1,200 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use cpal::Stream;
+use cpal::traits::StreamTrait;
+
+use crate::audio::InputConfig;
+use crate::decoder::DecoderOptions;
+use crate::error::Error;
+use crate::event::LtcEvent;
+
+/// Events waiting to be polled. When full, the oldest event makes room for the newest one.
+struct Queue {
+    items: VecDeque<Result<LtcEvent, Error>>,
+    capacity: usize,
+    lagged: u64,
+    dropped: u64,
+    waker: Option<Waker>,
+}
+
+impl Queue {
+    fn new(capacity: usize) -> Queue {
+        Queue {
+            items: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            lagged: 0,
+            dropped: 0,
+            waker: None,
+        }
+    }
+
+    /// Counts `count` events that were lost without being pushed.
+    fn lose(&mut self, count: u64) {
+        self.lagged += count;
+        self.dropped += count;
+    }
+
+    fn push(&mut self, item: Result<LtcEvent, Error>) {
+        if self.items.len() >= self.capacity {
+            self.items.pop_front();
+            self.lose(1);
+        }
+        self.items.push_back(item);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<LtcEvent, Error>>> {
+        if self.lagged > 0 {
+            let lagged = std::mem::take(&mut self.lagged);
+            return Poll::Ready(Some(Err(Error::Lagged(lagged))));
+        }
+        match self.items.pop_front() {
+            Some(item) => Poll::Ready(Some(item)),
+            None => {
+                self.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// The queue and the events the audio thread could not push because the consumer held the
+/// queue at that moment.
+struct Shared {
+    queue: Mutex<Queue>,
+    missed: AtomicU64,
+}
+
+impl Shared {
+    /// Locks the queue and counts the events missed since it was last locked.
+    fn lock(&self) -> std::sync::MutexGuard<'_, Queue> {
+        let mut queue = self.queue.lock().unwrap();
+        queue.lose(self.missed.swap(0, Ordering::Relaxed));
+        queue
+    }
+
+    /// Pushes an event from the audio thread, which never waits for the consumer.
+    fn push_event(&self, event: LtcEvent) {
+        match self.queue.try_lock() {
+            Ok(mut queue) => {
+                queue.lose(self.missed.swap(0, Ordering::Relaxed));
+                queue.push(Ok(event));
+            }
+            Err(_) => {
+                self.missed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// A running input stream whose events are delivered as a [`futures_core::Stream`], see
+/// [`connect_event_stream`].
+///
+/// The audio thread never waits for the consumer: when `capacity` events are pending, the oldest
+/// one is dropped, and an event that arrives while the consumer is polling is dropped as well.
+/// The next item polled after that is [`Error::Lagged`] with the number of events lost since
+/// the last one was taken.
+pub struct EventStream {
+    stream: Stream,
+    shared: Arc<Shared>,
+}
+
+/// Like [`connect_stream`](crate::connect_stream), but returns an [`EventStream`] holding up to
+/// `capacity` pending events for async consumers. Errors of the running stream are items of the
+/// stream as well, it never ends on its own.
+pub fn connect_event_stream(
+    device: &cpal::Device,
+    config: impl Into<InputConfig>,
+    options: DecoderOptions,
+    capacity: usize,
+) -> Result<EventStream, Error> {
+    let shared = Arc::new(Shared { queue: Mutex::new(Queue::new(capacity)), missed: AtomicU64::new(0) });
+    let event_shared = shared.clone();
+    let error_shared = shared.clone();
+    let stream = crate::connect_stream(
+        device,
+        config,
+        options,
+        move |event| event_shared.push_event(event),
+        // Errors are rare and must not be lost, so they wait for the consumer.
+        move |err| error_shared.lock().push(Err(err)),
+    )?;
+    Ok(EventStream { stream, shared })
+}
+
+impl EventStream {
+    /// Starts or resumes the input stream.
+    pub fn play(&self) -> Result<(), Error> {
+        Ok(self.stream.play()?)
+    }
+
+    /// Pauses the input stream, events that are already queued can still be polled.
+    pub fn pause(&self) -> Result<(), Error> {
+        Ok(self.stream.pause()?)
+    }
+
+    /// Number of events dropped so far because the consumer fell behind.
+    pub fn dropped(&self) -> u64 {
+        self.shared.lock().dropped
+    }
+}
+
+impl futures_core::Stream for EventStream {
+    type Item = Result<LtcEvent, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.shared.lock().poll(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::sync::atomic::AtomicU64;
+    use std::task::{Context, Poll, Waker};
+
+    use crate::{Error, LtcEvent};
+
+    use super::{Queue, Shared};
+
+    #[test]
+    fn drop_oldest() {
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut queue = Queue::new(2);
+        assert!(queue.poll(&mut cx).is_pending());
+        for position in 0..5 {
+            queue.push(Ok(LtcEvent::Locked { position }));
+        }
+        assert!(matches!(queue.poll(&mut cx), Poll::Ready(Some(Err(Error::Lagged(3))))));
+        assert!(matches!(queue.poll(&mut cx), Poll::Ready(Some(Ok(LtcEvent::Locked { position: 3 })))));
+        queue.push(Ok(LtcEvent::SignalLost { position: 5 }));
+        assert!(matches!(queue.poll(&mut cx), Poll::Ready(Some(Ok(LtcEvent::Locked { position: 4 })))));
+        assert!(matches!(queue.poll(&mut cx), Poll::Ready(Some(Ok(LtcEvent::SignalLost { position: 5 })))));
+        assert!(queue.poll(&mut cx).is_pending());
+        assert_eq!(queue.dropped, 3);
+    }
+
+    #[test]
+    fn count_missed() {
+        let mut cx = Context::from_waker(Waker::noop());
+        let shared = Shared { queue: Mutex::new(Queue::new(4)), missed: AtomicU64::new(0) };
+        shared.push_event(LtcEvent::Locked { position: 0 });
+        {
+            // The consumer is polling while the audio thread delivers two events.
+            let _queue = shared.queue.lock().unwrap();
+            shared.push_event(LtcEvent::Locked { position: 1 });
+            shared.push_event(LtcEvent::Locked { position: 2 });
+        }
+        let mut queue = shared.lock();
+        assert!(matches!(queue.poll(&mut cx), Poll::Ready(Some(Err(Error::Lagged(2))))));
+        assert!(matches!(queue.poll(&mut cx), Poll::Ready(Some(Ok(LtcEvent::Locked { position: 0 })))));
+        assert!(queue.poll(&mut cx).is_pending());
+        assert_eq!(queue.dropped, 2);
+    }
+}
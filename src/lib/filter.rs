@@ -9,3 +9,54 @@ mod fm_noise;
 mod raw_demod;
 mod raw_lowpass;
 mod raw_noise;
+
+/// Number of the smallest and of the largest values left out when measuring the range of the
+/// signal, so a single run across a dropout or a glitch does not throw off the thresholds.
+const OUTLIERS: usize = 2;
+
+/// The last `N` values a filter has seen, so its thresholds are measured over more than the
+/// current block when blocks are short.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct History<const N: usize> {
+    values: [i64; N],
+    len: usize,
+    next: usize,
+}
+
+impl<const N: usize> History<N> {
+    pub fn new() -> Self {
+        History { values: [0; N], len: 0, next: 0 }
+    }
+
+    pub fn extend(&mut self, data: &[i64]) {
+        for &value in &data[data.len().saturating_sub(N)..] {
+            self.values[self.next] = value;
+            self.next = (self.next + 1) % N;
+            self.len = (self.len + 1).min(N);
+        }
+    }
+
+    /// Smallest and largest of the history and `data` together, leaving out the outliers.
+    pub fn range(&self, data: &[i64]) -> (i64, i64) {
+        let mut low = [i64::MAX; OUTLIERS + 1];
+        let mut high = [i64::MIN; OUTLIERS + 1];
+        let mut count = 0;
+        for &value in self.values[..self.len].iter().chain(data) {
+            // Both arrays stay sorted, the most extreme value first.
+            if value < low[OUTLIERS] {
+                low[OUTLIERS] = value;
+                low.sort_unstable();
+            }
+            if value > high[OUTLIERS] {
+                high[OUTLIERS] = value;
+                high.sort_unstable_by(|a, b| b.cmp(a));
+            }
+            count += 1;
+        }
+        if count == 0 {
+            return (0, 0);
+        }
+        let index = OUTLIERS.min(count - 1);
+        (low[index], high[index])
+    }
+}
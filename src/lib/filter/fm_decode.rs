@@ -1,22 +1,62 @@
+use crate::filter::History;
+
+#[derive(Clone)]
 pub struct FmDecodeFilter {
     remaining: bool,
+    errors: u64,
+    history: History<128>,
 }
 
 impl FmDecodeFilter {
     pub fn new() -> FmDecodeFilter {
         return FmDecodeFilter {
             remaining: false,
+            errors: 0,
+            history: History::new(),
         };
     }
 
+    /// Number of biphase mark coding violations seen so far: a one whose second half was missing.
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    /// Whether both filters will decode the same bits from here on, regardless of the errors
+    /// counted so far.
+    #[cfg(feature = "std")]
+    pub(crate) fn same_state(&self, other: &FmDecodeFilter) -> bool {
+        self.remaining == other.remaining && self.history == other.history
+    }
+
+    #[cfg(feature = "std")]
     pub fn filter(&mut self, data: &Vec<i64>) -> Vec<bool> {
-        let min = *data.iter().min().unwrap_or(&0);
-        let max = *data.iter().max().unwrap_or(&0);
+        self.filter_indexed(data).into_iter()
+            .map(|(_, bit)| bit)
+            .collect()
+    }
+
+    /// Like [`filter`](Self::filter), but pairs every bit with the index of the element in
+    /// `data` that completed it.
+    #[cfg(feature = "std")]
+    pub fn filter_indexed(&mut self, data: &[i64]) -> Vec<(usize, bool)> {
+        let mut result = vec![(0, false); data.len()];
+        let count = self.filter_indexed_into(data, &mut result);
+        result.truncate(count);
+        result
+    }
+
+    /// Like `filter_indexed`, but writes into `out` and returns the number of bits written.
+    /// Never writes more bits than `data` has, so `out` is large enough if it is as long as
+    /// `data`.
+    pub fn filter_indexed_into(&mut self, data: &[i64], out: &mut [(usize, bool)]) -> usize {
+        let (min, max) = self.history.range(data);
+        self.history.extend(data);
         let threshold = (max - min) / 2;
-        data.into_iter().filter_map(|&val| {
+        let mut count = 0;
+        for (index, &val) in data.iter().enumerate() {
             let val = val - min;
             let is_zero = val > threshold;
-            if !is_zero {
+            let bit = if !is_zero {
                 if self.remaining {
                     self.remaining = false;
                     Some(true)
@@ -26,12 +66,19 @@ impl FmDecodeFilter {
                 }
             } else {
                 if self.remaining {
+                    // The half of a one is followed by a full zero cell.
                     self.remaining = false;
+                    self.errors += 1;
                     None
                 } else {
                     Some(false)
                 }
+            };
+            if let Some(bit) = bit {
+                out[count] = (index, bit);
+                count += 1;
             }
-        }).collect()
+        }
+        count
     }
 }
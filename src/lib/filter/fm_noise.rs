@@ -1,25 +1,51 @@
+use crate::filter::History;
+
+#[derive(Clone, PartialEq, Eq)]
 pub struct FmDenoiseFilter {
     last: i64,
+    history: History<64>,
 }
 
 impl FmDenoiseFilter {
     pub fn new() -> FmDenoiseFilter {
         return FmDenoiseFilter {
             last: 0,
+            history: History::new(),
         };
     }
 
+    #[cfg(feature = "std")]
     pub fn filter(&mut self, data: &Vec<i64>) -> Vec<i64> {
-        let bit_length = *data.iter().max().unwrap_or(&0);
-        data.into_iter().filter_map(|&val| {
+        self.filter_indexed(data).into_iter()
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// Like [`filter`](Self::filter), but pairs every value with the index of the element in
+    /// `data` that completed it.
+    #[cfg(feature = "std")]
+    pub fn filter_indexed(&mut self, data: &[i64]) -> Vec<(usize, i64)> {
+        let mut result = vec![(0, 0); data.len()];
+        let count = self.filter_indexed_into(data, &mut result);
+        result.truncate(count);
+        result
+    }
+
+    /// Like `filter_indexed`, but writes into `out` and returns the number of values written.
+    /// Never writes more values than `data` has, so `out` is large enough if it is as long as
+    /// `data`.
+    pub fn filter_indexed_into(&mut self, data: &[i64], out: &mut [(usize, i64)]) -> usize {
+        let (_, bit_length) = self.history.range(data);
+        self.history.extend(data);
+        let mut count = 0;
+        for (index, &val) in data.iter().enumerate() {
             self.last += val;
             if self.last > bit_length / 4 {
-                let value = self.last;
+                out[count] = (index, self.last);
+                count += 1;
                 self.last = 0;
-                Some(value)
-            } else {
-                None
             }
-        }).collect()
+        }
+        count
     }
-}
\ No newline at end of file
+}
@@ -1,3 +1,4 @@
+#[derive(Clone, PartialEq, Eq)]
 pub struct RawDemodFilter {
     last: i8,
     count: i64,
@@ -11,17 +12,33 @@ impl RawDemodFilter {
         };
     }
 
+    #[cfg(feature = "std")]
     pub fn filter(&mut self, data: &Vec<i8>) -> Vec<i64> {
-        data.into_iter().filter_map(|&val| {
-            if val == self.last {
-                self.count += 1;
-                None
-            } else {
-                let count = self.count;
-                self.count = 0;
-                self.last = val;
-                Some(count)
-            }
-        }).collect()
+        self.filter_indexed(data).into_iter()
+            .map(|(_, count)| count)
+            .collect()
     }
-}
\ No newline at end of file
+
+    /// Like [`filter`](Self::filter), but pairs every run length with the index of the sample
+    /// in `data` at which the run ended.
+    #[cfg(feature = "std")]
+    pub fn filter_indexed(&mut self, data: &[i8]) -> Vec<(usize, i64)> {
+        data.iter().enumerate()
+            .filter_map(|(index, &val)| self.push(val).map(|count| (index, count)))
+            .collect()
+    }
+
+    /// Filters a single sample, returns the length of the previous run if this sample starts a
+    /// new one.
+    pub fn push(&mut self, val: i8) -> Option<i64> {
+        if val == self.last {
+            self.count += 1;
+            None
+        } else {
+            let count = self.count;
+            self.count = 0;
+            self.last = val;
+            Some(count)
+        }
+    }
+}
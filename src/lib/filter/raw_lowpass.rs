@@ -1,3 +1,4 @@
+#[derive(Clone, PartialEq)]
 pub struct RawLowpassFilter {
     last: [f32; 4],
 }
@@ -9,11 +10,15 @@ impl RawLowpassFilter {
         };
     }
 
+    #[cfg(feature = "std")]
     pub fn filter(&mut self, data: &Vec<f32>) -> Vec<f32> {
-        return data.iter().map(|&val| {
-            let next = (self.last[0] + self.last[1] + self.last[2] + self.last[3] + val) / 5.0;
-            self.last = [self.last[1], self.last[2], self.last[3], val];
-            next
-        }).collect();
+        return data.iter().map(|&val| self.push(val)).collect();
     }
-}
\ No newline at end of file
+
+    /// Filters a single sample.
+    pub fn push(&mut self, val: f32) -> f32 {
+        let next = (self.last[0] + self.last[1] + self.last[2] + self.last[3] + val) / 5.0;
+        self.last = [self.last[1], self.last[2], self.last[3], val];
+        next
+    }
+}
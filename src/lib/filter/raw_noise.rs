@@ -1,3 +1,4 @@
+#[derive(Clone, PartialEq, Eq)]
 pub struct RawDenoiseFilter {}
 
 impl RawDenoiseFilter {
@@ -5,9 +6,13 @@ impl RawDenoiseFilter {
         return RawDenoiseFilter {};
     }
 
+    #[cfg(feature = "std")]
     pub fn filter(&mut self, data: &Vec<f32>) -> Vec<i8> {
-        return data.iter().map(|&val| {
-            if val > 0.0 { 1 } else { -1 }
-        }).collect();
+        return data.iter().map(|&val| self.push(val)).collect();
     }
-}
\ No newline at end of file
+
+    /// Filters a single sample.
+    pub fn push(&mut self, val: f32) -> i8 {
+        if val > 0.0 { 1 } else { -1 }
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::timecode::{frame_separator, Timecode};
+
+/// How to turn a [`Timecode`] into text.
+#[derive(Eq, PartialEq, Clone, Debug, Default)]
+pub enum TimecodeFormat {
+    /// `HH:MM:SS:FF`, or `HH:MM:SS;FF` for drop-frame timecode. Same as `Display`.
+    #[default]
+    Smpte,
+    /// Number of frames since midnight.
+    FrameCount,
+    /// Seconds since midnight with milliseconds, e.g. `68530.280`. Drop-frame timecode counts
+    /// real time at 1000/1001 of the nominal frame rate.
+    Seconds,
+    /// A template in which the following placeholders are replaced:
+    ///
+    /// - `%H`, `%M`, `%S`, `%F`: hours, minutes, seconds and frames, two digits each
+    /// - `%;`: the separator before the frames, `:` or `;` for drop-frame timecode
+    /// - `%N`: number of frames since midnight
+    /// - `%L`: milliseconds into the current second, three digits
+    /// - `%%`: a literal `%`
+    Template(String),
+}
+
+impl TimecodeFormat {
+    pub fn format(&self, timecode: &Timecode, fps: u128) -> String {
+        match self {
+            TimecodeFormat::Smpte => timecode.to_string(),
+            TimecodeFormat::FrameCount => timecode.to_frame_count(fps).to_string(),
+            TimecodeFormat::Seconds => {
+                let (num, den) = if timecode.is_drop() { (1001, 1000) } else { (1, 1) };
+                let millis = (timecode.to_frame_count(fps) as u128 * 1000 * num)
+                    .checked_div(fps * den)
+                    .unwrap_or(0);
+                format!("{}.{:03}", millis / 1000, millis % 1000)
+            }
+            TimecodeFormat::Template(template) => {
+                let mut result = String::new();
+                let mut chars = template.chars();
+                while let Some(char) = chars.next() {
+                    if char != '%' {
+                        result.push(char);
+                        continue;
+                    }
+                    let _ = match chars.next() {
+                        Some('H') => write!(result, "{:02}", timecode.hour()),
+                        Some('M') => write!(result, "{:02}", timecode.minute()),
+                        Some('S') => write!(result, "{:02}", timecode.second()),
+                        Some('F') => write!(result, "{:02}", timecode.frame()),
+                        Some(';') => write!(result, "{}", frame_separator(timecode)),
+                        Some('N') => write!(result, "{}", timecode.to_frame_count(fps)),
+                        Some('L') => write!(result, "{:03}", (timecode.frame() as u128 * 1000)
+                            .checked_div(fps)
+                            .unwrap_or(0)),
+                        Some('%') => write!(result, "%"),
+                        Some(other) => write!(result, "%{}", other),
+                        None => write!(result, "%"),
+                    };
+                }
+                result
+            }
+        }
+    }
+}
+
+impl FromStr for TimecodeFormat {
+    type Err = String;
+
+    /// Parses `smpte`, `frames` or `seconds`, anything containing a `%` is taken as a
+    /// [template](TimecodeFormat::Template).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "smpte" => Ok(TimecodeFormat::Smpte),
+            "frames" => Ok(TimecodeFormat::FrameCount),
+            "seconds" => Ok(TimecodeFormat::Seconds),
+            template if template.contains('%') => Ok(TimecodeFormat::Template(template.to_string())),
+            other => Err(format!("unknown timecode format \"{}\", expected smpte, frames, seconds or a template with % placeholders", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Timecode, TimecodeFormat};
+
+    #[test]
+    fn format() {
+        let timecode = Timecode::new(19, 12, 10, 7, false);
+        let drop = Timecode::new(1, 8, 59, 28, true);
+        assert_eq!(timecode.to_string(), "19:12:10:07");
+        assert_eq!(drop.to_string(), "01:08:59;28");
+        assert_eq!(TimecodeFormat::FrameCount.format(&timecode, 25), "1728257");
+        assert_eq!(TimecodeFormat::Seconds.format(&timecode, 25), "69130.280");
+        let template: TimecodeFormat = "%Hh%Mm%Ss%;%F (%L ms, 100%%)".parse().unwrap();
+        assert_eq!(template.format(&drop, 30), "01h08m59s;28 (933 ms, 100%)");
+    }
+}
@@ -0,0 +1,93 @@
+use crate::decoder::DecodedFrame;
+use crate::timecode::Timecode;
+
+/// Keeps timecode running across short LTC dropouts.
+///
+/// After every real frame, the freewheel waits for the next one to show up. Once a frame is
+/// overdue by half a frame period, it generates the missing frame from the last good one and the
+/// measured frame period instead, up to `max_frames` frames in a row. Generated frames are marked
+/// as [interpolated](crate::LtcFrame::is_interpolated). The next real frame re-locks it, after
+/// the frames between it and the last one that are still missing have been generated.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Freewheel {
+    max_frames: u32,
+    last: Option<DecodedFrame>,
+    period: Option<u64>,
+    generated: u32,
+}
+
+impl Freewheel {
+    pub fn new(max_frames: u32) -> Freewheel {
+        Freewheel {
+            max_frames,
+            last: None,
+            period: None,
+            generated: 0,
+        }
+    }
+
+    /// Re-locks to a frame decoded from the signal.
+    pub fn lock(&mut self, frame: &DecodedFrame) {
+        if let Some(last) = &self.last {
+            let consecutive = last.frame.timecode().next(frame.fps) == frame.frame.timecode();
+            if self.generated == 0 && consecutive && frame.position > last.position {
+                self.period = Some(frame.position - last.position);
+            }
+        }
+        self.last = Some(frame.clone());
+        self.generated = 0;
+    }
+
+    /// Generates the frames missing between the last frame and `frame`, which was decoded from
+    /// the signal. Once the timecode jumps, only the frames that were due before `frame`
+    /// started are generated, like [`fill`](Self::fill) does.
+    pub fn fill_before(&mut self, frame: &DecodedFrame) -> Vec<DecodedFrame> {
+        let mut result = vec![];
+        let (last, period) = match (&self.last, self.period) {
+            (Some(last), Some(period)) if last.fps > 0 => (last, period),
+            _ => return result,
+        };
+        let day = Timecode::frames_per_day(last.fps, last.frame.is_drop()).max(1);
+        let missing = (frame.frame.timecode().to_frame_count(last.fps) + day
+            - last.frame.timecode().to_frame_count(last.fps) % day) % day;
+        if missing == 0 || missing > self.max_frames as u64 + 1 {
+            return self.fill(frame.position);
+        }
+        while (self.generated as u64) + 1 < missing {
+            let index = self.generated as u64 + 1;
+            let timecode = last.frame.timecode().offset(index as i64, last.fps);
+            result.push(DecodedFrame {
+                frame: last.frame.interpolate(timecode),
+                fps: last.fps,
+                position: last.position + index * period,
+            });
+            self.generated += 1;
+        }
+        result
+    }
+
+    /// Generates all frames that should have been decoded by sample `position`, but were not.
+    pub fn fill(&mut self, position: u64) -> Vec<DecodedFrame> {
+        let mut result = vec![];
+        let (last, period) = match (&self.last, self.period) {
+            (Some(last), Some(period)) if last.fps > 0 => (last, period),
+            _ => return result,
+        };
+        while self.generated < self.max_frames {
+            let index = self.generated as u64 + 1;
+            // A frame is only decoded once it has been received completely, so the frame starting
+            // at `index` periods is due one period later.
+            if position < last.position + (index + 1) * period + period / 2 {
+                break;
+            }
+            let timecode = last.frame.timecode().offset(index as i64, last.fps);
+            result.push(DecodedFrame {
+                frame: last.frame.interpolate(timecode),
+                fps: last.fps,
+                position: last.position + index * period,
+            });
+            self.generated += 1;
+        }
+        result
+    }
+}
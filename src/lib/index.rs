@@ -0,0 +1,169 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::error::Error;
+use crate::segments::{split_segments, Segment, Segments};
+use crate::source::SourceEvents;
+use crate::timecode::Timecode;
+
+const MAGIC: &str = "ltc-index";
+const VERSION: u32 = 1;
+
+/// Maps timecode to sample positions of a recording and back, without decoding it again.
+///
+/// The index holds the continuous segments of the recording, see [`split_segments`], and
+/// interpolates linearly within a segment, so a recording that runs slightly fast or slow is
+/// still mapped to the sample. It is stored as text: a header line `ltc-index 1 SAMPLE_RATE`,
+/// then one line `START END START_SAMPLE END_SAMPLE FRAMES FPS` per segment, with timecodes
+/// written like `HH:MM:SS:FF`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TimecodeIndex {
+    sample_rate: u32,
+    segments: Vec<Segment>,
+}
+
+impl From<Segments> for TimecodeIndex {
+    fn from(segments: Segments) -> Self {
+        TimecodeIndex { sample_rate: segments.sample_rate, segments: segments.segments }
+    }
+}
+
+impl TimecodeIndex {
+    /// Indexes what was decoded from a whole recording.
+    pub fn build<E: SourceEvents>(events: E) -> Result<TimecodeIndex, Error> {
+        split_segments(events).map(TimecodeIndex::from)
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// Sample at which `timecode` starts, from the first segment that contains it.
+    pub fn sample_of(&self, timecode: Timecode) -> Option<u64> {
+        self.segments.iter().find_map(|segment| {
+            let (offset, frames) = segment_offset(segment, timecode.to_frame_count(segment.fps))?;
+            let samples = segment.end_sample - segment.start_sample;
+            Some(segment.start_sample + (offset as u128 * samples as u128 / frames as u128) as u64)
+        })
+    }
+
+    /// Timecode of the frame that sample `sample` belongs to, or `None` if it lies outside of
+    /// all segments.
+    pub fn timecode_at(&self, sample: u64) -> Option<Timecode> {
+        let segment = self.segments.iter()
+            .find(|segment| (segment.start_sample..segment.end_sample).contains(&sample))?;
+        let frames = segment_frames(segment).filter(|&it| it > 0)?;
+        let samples = segment.end_sample - segment.start_sample;
+        let offset = (sample - segment.start_sample) as u128 * frames as u128 / samples as u128;
+        Some(segment.start.offset(offset as i64, segment.fps))
+    }
+
+    /// Reads an index written by [`write`](Self::write).
+    pub fn read<R: BufRead>(reader: R) -> Result<TimecodeIndex, Error> {
+        let invalid = |line: usize, reason: &str| Error::InvalidFile(format!("timecode index line {}: {}", line + 1, reason));
+        let mut lines = reader.lines();
+        let header = lines.next().transpose()?.unwrap_or_default();
+        let sample_rate = match header.split_whitespace().collect::<Vec<_>>()[..] {
+            [MAGIC, version, sample_rate] if version.parse() == Ok(VERSION) =>
+                sample_rate.parse().map_err(|_| invalid(0, "invalid sample rate"))?,
+            [MAGIC, ..] => return Err(invalid(0, "unsupported version")),
+            _ => return Err(invalid(0, "not a timecode index")),
+        };
+        let mut segments = vec![];
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [start, end, start_sample, end_sample, frames, fps] = fields[..] else {
+                return Err(invalid(index + 1, "expected 6 fields"));
+            };
+            let timecode = |value: &str| value.parse::<Timecode>().map_err(|err| invalid(index + 1, &err.to_string()));
+            let number = |value: &str| value.parse::<u64>().map_err(|_| invalid(index + 1, &format!("\"{}\" is not a number", value)));
+            let start = timecode(start)?;
+            let segment = Segment {
+                start,
+                end: timecode(end)?,
+                start_sample: number(start_sample)?,
+                end_sample: number(end_sample)?,
+                frames: number(frames)?,
+                fps: number(fps)? as u128,
+                drop: start.is_drop(),
+            };
+            if segment.end_sample <= segment.start_sample {
+                return Err(invalid(index + 1, "segment ends before it starts"));
+            }
+            segments.push(segment);
+        }
+        Ok(TimecodeIndex { sample_rate, segments })
+    }
+
+    /// Writes the index in the format described on [`TimecodeIndex`].
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        writeln!(writer, "{} {} {}", MAGIC, VERSION, self.sample_rate)?;
+        for segment in &self.segments {
+            writeln!(
+                writer, "{} {} {} {} {} {}",
+                segment.start, segment.end, segment.start_sample, segment.end_sample, segment.frames, segment.fps,
+            )?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<TimecodeIndex, Error> {
+        TimecodeIndex::read(BufReader::new(File::open(path)?))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+}
+
+/// Number of frames the timecode of `segment` spans, which may run past midnight. `None` if
+/// the frame rate is unknown.
+fn segment_frames(segment: &Segment) -> Option<u64> {
+    let day = Timecode::frames_per_day(segment.fps, segment.drop);
+    let start = segment.start.to_frame_count(segment.fps);
+    (day > 0).then(|| (segment.end.to_frame_count(segment.fps) + day - start) % day)
+}
+
+/// How many frames `count` lies after the start of `segment`, and how many frames the segment
+/// spans, or `None` if `count` is outside of the segment.
+fn segment_offset(segment: &Segment, count: u64) -> Option<(u64, u64)> {
+    let frames = segment_frames(segment)?;
+    let day = Timecode::frames_per_day(segment.fps, segment.drop);
+    let offset = (count % day + day - segment.start.to_frame_count(segment.fps)) % day;
+    (offset < frames).then_some((offset, frames))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{read_test_file, test_source, Err};
+    use crate::{decode_source, DecoderOptions, Error, Timecode, TimecodeIndex};
+
+    #[test]
+    fn lookup() -> Result<(), Err> {
+        let samples = read_test_file()?;
+        let index = TimecodeIndex::build(decode_source(test_source(&samples), DecoderOptions::default()))?;
+
+        let mut file = vec![];
+        index.write(&mut file)?;
+        assert_eq!(String::from_utf8(file.clone())?, "ltc-index 1 88200\n19:12:10:07 19:12:11:10 2653 101356 28 25\n");
+        assert_eq!(TimecodeIndex::read(file.as_slice())?, index);
+        assert!(matches!(TimecodeIndex::read(b"ltc-index 1 88200\n19:12:10:07 19:12:11:10 2653\n".as_slice()), Err(Error::InvalidFile(_))));
+
+        assert_eq!(index.sample_of(Timecode::new(19, 12, 10, 7, false)), Some(2653));
+        // 18 frames of 3525 samples on average.
+        assert_eq!(index.sample_of(Timecode::new(19, 12, 11, 0, false)), Some(2653 + 18 * 98703 / 28));
+        assert_eq!(index.sample_of(Timecode::new(19, 12, 11, 10, false)), None);
+        assert_eq!(index.timecode_at(2653), Some(Timecode::new(19, 12, 10, 7, false)));
+        assert_eq!(index.timecode_at(66107), Some(Timecode::new(19, 12, 11, 0, false)));
+        assert_eq!(index.timecode_at(101355), Some(Timecode::new(19, 12, 11, 9, false)));
+        assert_eq!(index.timecode_at(2652), None);
+        Ok(())
+    }
+}
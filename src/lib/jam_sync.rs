@@ -0,0 +1,137 @@
+use crate::decoder::DecodedFrame;
+use crate::timecode::Timecode;
+
+/// Output of a [`JamSync`] clock, serialized like [`LtcEvent`](crate::LtcEvent).
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum JamSyncEvent {
+    /// The clock was jammed to this frame of the incoming timecode.
+    Jammed(DecodedFrame),
+    /// A frame generated by the internal clock.
+    Frame(DecodedFrame),
+    /// Incoming timecode came back after a dropout. `samples` is how far the source is behind
+    /// the internal clock, negative if it is ahead.
+    Drift { position: u64, samples: i64 },
+}
+
+struct Origin {
+    frame: DecodedFrame,
+    count: u64,
+    samples_per_frame: (u128, u128),
+}
+
+impl Origin {
+    fn position_of(&self, frames: i64) -> i64 {
+        let (num, den) = self.samples_per_frame;
+        let offset = (frames.unsigned_abs() as u128 * num / den) as i64;
+        self.frame.position as i64 + offset * frames.signum()
+    }
+}
+
+/// An internal timecode clock, driven by the audio sample clock.
+///
+/// The clock locks ("jams") to the first frame of incoming timecode it sees, and from then on
+/// generates one frame per frame period of the input sample rate, no matter whether the
+/// incoming timecode keeps arriving. Drop-frame timecode runs at the NTSC rate of 1000/1001 of
+/// its nominal frame rate.
+pub struct JamSync {
+    sample_rate: u32,
+    origin: Option<Origin>,
+    generated: u64,
+    last_source: Option<u64>,
+    drift: Option<i64>,
+}
+
+impl JamSync {
+    pub fn new(sample_rate: u32) -> JamSync {
+        JamSync {
+            sample_rate,
+            origin: None,
+            generated: 0,
+            last_source: None,
+            drift: None,
+        }
+    }
+
+    #[cfg(feature = "audio")]
+    pub fn for_config(config: &cpal::StreamConfig) -> JamSync {
+        JamSync::new(config.sample_rate.0)
+    }
+
+    pub fn is_jammed(&self) -> bool {
+        self.origin.is_some()
+    }
+
+    /// Most recently measured drift of the incoming timecode in samples, see
+    /// [`JamSyncEvent::Drift`].
+    pub fn drift(&self) -> Option<i64> {
+        self.drift
+    }
+
+    /// Unlocks the clock, it will be jammed again to the next incoming frame.
+    pub fn rejam(&mut self) {
+        self.origin = None;
+        self.generated = 0;
+        self.last_source = None;
+        self.drift = None;
+    }
+
+    /// Feeds the frames decoded from a block of samples, and advances the clock up to sample
+    /// `position`, the end of that block.
+    pub fn process(&mut self, frames: &[DecodedFrame], position: u64) -> Vec<JamSyncEvent> {
+        let mut result = vec![];
+        for frame in frames.iter().filter(|it| !it.frame.is_interpolated()) {
+            match &self.origin {
+                None if frame.fps > 0 => {
+                    let drop = if frame.frame.is_drop() { 1001 } else { 1000 };
+                    self.origin = Some(Origin {
+                        frame: frame.clone(),
+                        count: frame.frame.timecode().to_frame_count(frame.fps),
+                        samples_per_frame: (self.sample_rate as u128 * drop, frame.fps * 1000),
+                    });
+                    self.generated = 0;
+                    result.push(JamSyncEvent::Jammed(frame.clone()));
+                }
+                None => {}
+                Some(origin) => {
+                    // The nearest frame with this label, so timecode that wraps at midnight
+                    // is not taken for a jump back by a whole day.
+                    let day = Timecode::frames_per_day(origin.frame.fps, frame.frame.is_drop()).max(1) as i64;
+                    let mut frames = (frame.frame.timecode().to_frame_count(origin.frame.fps) as i64
+                        - origin.count as i64).rem_euclid(day);
+                    if frames > day / 2 {
+                        frames -= day;
+                    }
+                    let drift = frame.position as i64 - origin.position_of(frames);
+                    self.drift = Some(drift);
+                    let dropout = origin.position_of(2) - origin.position_of(0);
+                    if let Some(last) = self.last_source {
+                        if frame.position.saturating_sub(last) > dropout as u64 {
+                            result.push(JamSyncEvent::Drift { position: frame.position, samples: drift });
+                        }
+                    }
+                }
+            }
+            self.last_source = Some(frame.position);
+        }
+
+        if let Some(origin) = &self.origin {
+            loop {
+                let index = self.generated as i64 + 1;
+                let frame_position = origin.position_of(index) as u64;
+                if frame_position > position {
+                    break;
+                }
+                let timecode = origin.frame.frame.timecode().offset(index, origin.frame.fps);
+                result.push(JamSyncEvent::Frame(DecodedFrame {
+                    frame: origin.frame.frame.interpolate(timecode),
+                    fps: origin.frame.fps,
+                    position: frame_position,
+                }));
+                self.generated += 1;
+            }
+        }
+        result
+    }
+}
@@ -1,62 +1,166 @@
-use cpal::{BuildStreamError, Stream};
-use cpal::traits::DeviceTrait;
+#![cfg_attr(not(feature = "std"), no_std)]
 
+//! Decodes SMPTE linear timecode (LTC) from audio.
+//!
+//! The filters in [`filter`], [`LtcFrameReader`], [`LtcFrame`], [`Timecode`] and [`Userdata`]
+//! work without `std` and without an allocator: disable the default features to use them on
+//! embedded targets. Everything else needs the `std` feature, and reading from audio devices
+//! through `cpal` the `audio` feature. Files and pipes are decoded through a [`SampleSource`]
+//! like [`WavReader`] or [`RawReader`], or `MediaReader` for compressed and container audio with the `media`
+//! feature.
+
+#[cfg(feature = "std")]
+pub use analysis::{analyze, Analysis, Discontinuity, Dropout, UserdataChange};
+#[cfg(feature = "audio")]
+pub use audio::{connect_jam_sync, connect_stream, InputConfig};
+#[cfg(feature = "audio")]
+pub use channel::{connect_channel, EventIter, EventReceiver};
+#[cfg(feature = "std")]
+pub use bwf::{stamp_bwf, BwfTimecode, BwfWriter};
+#[cfg(feature = "std")]
+pub use continuity::{Continuity, ContinuityDetector};
+#[cfg(feature = "std")]
+pub use decoder::{DecodedFrame, Decoder, DecoderOptions};
+#[cfg(feature = "std")]
+pub use error::Error;
+#[cfg(feature = "async")]
+pub use event_stream::{connect_event_stream, EventStream};
+#[cfg(feature = "std")]
+pub use event::LtcEvent;
+#[cfg(feature = "std")]
+pub use format::TimecodeFormat;
+#[cfg(feature = "std")]
+pub use freewheel::Freewheel;
+#[cfg(feature = "std")]
+pub use index::TimecodeIndex;
+#[cfg(feature = "std")]
+pub use jam_sync::{JamSync, JamSyncEvent};
 pub use ltc_frame::LtcFrame;
+#[cfg(feature = "media")]
+pub use media::{MediaReader, MediaTrack};
+#[cfg(feature = "std")]
+pub use parallel::{decode_source_parallel, ParallelSourceDecoder};
+#[cfg(feature = "std")]
+pub use raw::{PcmFormat, RawReader};
 pub use reader::LtcFrameReader;
+#[cfg(feature = "audio")]
+pub use record::{connect_recorder, Recorder};
+#[cfg(feature = "std")]
+pub use segments::{split_segments, Segment, Segments};
+#[cfg(feature = "std")]
+pub use source::{decode_source, SampleSource, SourceDecoder, SourceEvents};
+pub use timecode::Timecode;
+#[cfg(feature = "std")]
+pub use subtitles::{write_subtitles, SubtitleFormat};
+#[cfg(feature = "std")]
+pub use sync::{locate, sync, Recording, SyncEntry, SyncReport};
+#[cfg(feature = "std")]
+pub use timecode::ParseTimecodeError;
+pub use userdata::{DateTimeZone, Userdata};
+#[cfg(feature = "std")]
+pub use wav::{WavFormat, WavReader};
 
 pub mod filter;
+#[cfg(feature = "std")]
+mod analysis;
+#[cfg(feature = "audio")]
+mod audio;
+#[cfg(feature = "std")]
+mod bwf;
+#[cfg(feature = "audio")]
+mod channel;
+#[cfg(feature = "std")]
+mod continuity;
+#[cfg(feature = "std")]
+mod decoder;
+#[cfg(feature = "std")]
+mod edl;
+#[cfg(feature = "std")]
+mod error;
+#[cfg(feature = "std")]
+mod event;
+#[cfg(feature = "async")]
+mod event_stream;
+#[cfg(feature = "std")]
+mod format;
+#[cfg(feature = "std")]
+mod freewheel;
+#[cfg(feature = "std")]
+mod index;
+#[cfg(feature = "std")]
+mod jam_sync;
+#[cfg(feature = "std")]
+mod parallel;
+#[cfg(feature = "std")]
+mod raw;
 mod reader;
+#[cfg(feature = "audio")]
+mod record;
 mod ltc_frame;
+#[cfg(feature = "media")]
+mod media;
+#[cfg(feature = "std")]
+mod segments;
+#[cfg(feature = "std")]
+mod source;
+#[cfg(feature = "std")]
+mod subtitles;
+#[cfg(feature = "std")]
+mod sync;
+mod timecode;
+mod userdata;
+#[cfg(feature = "std")]
+mod wav;
 
-
-pub fn connect_stream<Callback, CallbackError>(
-    device: &cpal::Device,
-    config: &cpal::StreamConfig,
-    callback: Callback,
-    error_callback: CallbackError,
-) -> Result<Stream, BuildStreamError>
-    where Callback: Fn((LtcFrame, u128)) + Send + 'static,
-          CallbackError: Fn(cpal::StreamError) + Send + 'static,
-{
-    let mut filter_lowpass = filter::RawLowpassFilter::new();
-    let mut filter_denoise = filter::RawDenoiseFilter::new();
-    let mut filter_demod = filter::RawDemodFilter::new();
-    let mut filter_fm_denoise = filter::FmDenoiseFilter::new();
-    let mut filter_fm_decode = filter::FmDecodeFilter::new();
-    let mut frame_reader = LtcFrameReader::new();
-
-    device.build_input_stream(
-        config,
-        move |data: &[f32], _info: &cpal::InputCallbackInfo| {
-            let data = data.to_vec();
-            let data = filter_lowpass.filter(&data);
-            let data = filter_denoise.filter(&data);
-            let data = filter_demod.filter(&data);
-            let data = filter_fm_denoise.filter(&data);
-            let data = filter_fm_decode.filter(&data);
-            let data = frame_reader.read(&data);
-            let fps_actual = frame_reader.frame_rate()
-                .unwrap_or(0);
-            for frame in data {
-                callback((frame, fps_actual));
-            }
-        },
-        move |err| {
-            error_callback(err)
-        },
-        None,
-    )
-}
-
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use std::error::Error;
     use std::fs::File;
+    use std::io::Cursor;
 
     use crate::filter::{FmDecodeFilter, FmDenoiseFilter, RawDemodFilter, RawDenoiseFilter, RawLowpassFilter};
-    use crate::LtcFrameReader;
+    use crate::freewheel::Freewheel;
+    use crate::{decode_source, Continuity, DecodedFrame, Decoder, DecoderOptions, JamSync, JamSyncEvent, LtcEvent, LtcFrame, LtcFrameReader, PcmFormat, RawReader, SampleSource, Timecode};
+
+    pub(crate) type Err = Box<dyn Error>;
+
+    pub(crate) fn read_test_file() -> Result<Vec<f32>, Err> {
+        let file = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test.json");
+        let file = File::open(file)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// `samples` as raw PCM at the sample rate of the test file.
+    pub(crate) fn test_source(samples: &[f32]) -> RawReader<Cursor<Vec<u8>>> {
+        let bytes = samples.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+        RawReader::new(Cursor::new(bytes), PcmFormat::F32Le, 88200, 1)
+    }
+
+    /// Positions and timecodes of the frames decoded from all of `source`.
+    pub(crate) fn source_frames<S: SampleSource>(source: S) -> Result<Vec<(u64, Timecode)>, crate::Error> {
+        decode_source(source, DecoderOptions::default())
+            .filter_map(|event| event.map(LtcEvent::into_frame).transpose())
+            .map(|frame| frame.map(|frame| (frame.position, frame.frame.timecode())))
+            .collect()
+    }
+
+    fn decode_frames(decoder: &mut Decoder, data: &[f32]) -> Vec<DecodedFrame> {
+        decoder.decode(data).into_iter()
+            .filter_map(LtcEvent::into_frame)
+            .collect()
+    }
 
-    type Err = Box<dyn Error>;
+    /// A frame at 25 fps decoded from the signal, with `timecode` and no userdata.
+    fn frame_at(timecode: Timecode, position: u64) -> DecodedFrame {
+        let mut bits = [false; 80];
+        for (start, value) in [(0, timecode.frame()), (16, timecode.second()), (32, timecode.minute()), (48, timecode.hour())] {
+            for bit in 0..4 {
+                bits[start + bit] = (value % 10) >> bit & 1 == 1;
+                bits[start + 8 + bit] = bit < 3 && (value / 10) >> bit & 1 == 1;
+            }
+        }
+        DecodedFrame { frame: LtcFrame::read(&bits), fps: 25, position }
+    }
 
     #[test]
     fn foobar() -> Result<(), Err> {
@@ -146,4 +250,203 @@ mod tests {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn fixed_buffers() -> Result<(), Err> {
+        let data = read_test_file()?;
+        let expected: Vec<_> = Decoder::new(DecoderOptions::default()).decode(&data).into_iter()
+            .filter_map(LtcEvent::into_frame)
+            .map(|frame| (frame.position, frame.frame))
+            .collect();
+
+        let mut lowpass = RawLowpassFilter::new();
+        let mut denoise = RawDenoiseFilter::new();
+        let mut demod = RawDemodFilter::new();
+        let mut fm_denoise = FmDenoiseFilter::new();
+        let mut fm_decode = FmDecodeFilter::new();
+        let mut reader = LtcFrameReader::new();
+        let mut runs = [(0u64, 0i64); 4096];
+        let mut cells = [(0usize, 0i64); 4096];
+        let mut bits = [(0usize, false); 4096];
+        let mut run_count = 0;
+        let mut frames = vec![];
+        for (position, &sample) in data.iter().enumerate() {
+            if let Some(run) = demod.push(denoise.push(lowpass.push(sample))) {
+                runs[run_count] = (position as u64, run);
+                run_count += 1;
+            }
+            if run_count == runs.len() || position == data.len() - 1 {
+                let lengths = runs.map(|(_, run)| run);
+                let cell_count = fm_denoise.filter_indexed_into(&lengths[..run_count], &mut cells);
+                let lengths = cells.map(|(_, cell)| cell);
+                let bit_count = fm_decode.filter_indexed_into(&lengths[..cell_count], &mut bits);
+                for &(cell, bit) in &bits[..bit_count] {
+                    frames.extend(reader.push(runs[cells[cell].0].0, bit));
+                }
+                run_count = 0;
+            }
+        }
+        assert_eq!(frames.len(), expected.len());
+        assert_eq!(frames, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn freewheel() -> Result<(), Err> {
+        let file = read_test_file()?;
+        let mut decoder = Decoder::new(DecoderOptions::default());
+        let frames = decode_frames(&mut decoder, &file);
+        let period = frames[2].position - frames[1].position;
+        assert!(frames.windows(2).all(|pair| pair[1].position - pair[0].position <= period + 1));
+
+        // Silence two frames once the frame rate is known
+        let gap_start = frames[frames.len() - 8].position as usize;
+        let mut file = file;
+        file[gap_start..gap_start + 2 * period as usize].fill(0.0);
+        for chunk_size in [4096, file.len()] {
+            let mut decoder = Decoder::new(DecoderOptions { freewheel_frames: 4, ..DecoderOptions::default() });
+            let mut filled = vec![];
+            for chunk in file.chunks(chunk_size) {
+                filled.extend(decode_frames(&mut decoder, chunk));
+            }
+            let filled: Vec<_> = filled.into_iter().filter(|it| it.fps > 0).collect();
+            let interpolated: Vec<_> = filled.iter().filter(|it| it.frame.is_interpolated())
+                .map(|it| it.frame.timecode())
+                .collect();
+            assert_eq!(interpolated, vec![frames[frames.len() - 8].frame.timecode(), frames[frames.len() - 7].frame.timecode()]);
+            assert!(filled.windows(2).all(|pair| {
+                pair[0].frame.timecode().next(pair[0].fps) == pair[1].frame.timecode()
+            }));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn freewheel_gap() {
+        // Two frames lock the period, then the signal is gone until frame 4 comes in.
+        let mut freewheel = Freewheel::new(4);
+        freewheel.lock(&frame_at(Timecode::new(1, 0, 0, 0, false), 0));
+        freewheel.lock(&frame_at(Timecode::new(1, 0, 0, 1, false), 1000));
+        let real = frame_at(Timecode::new(1, 0, 0, 4, false), 4000);
+        let filled: Vec<_> = freewheel.fill_before(&real).into_iter()
+            .map(|frame| (frame.position, frame.frame.timecode(), frame.frame.is_interpolated()))
+            .collect();
+        assert_eq!(filled, vec![
+            (2000, Timecode::new(1, 0, 0, 2, false), true),
+            (3000, Timecode::new(1, 0, 0, 3, false), true),
+        ]);
+        freewheel.lock(&real);
+        assert!(freewheel.fill(4900).is_empty());
+
+        // Frames generated before the real frame came in are not generated again.
+        let mut freewheel = Freewheel::new(4);
+        freewheel.lock(&frame_at(Timecode::new(1, 0, 0, 0, false), 0));
+        freewheel.lock(&frame_at(Timecode::new(1, 0, 0, 1, false), 1000));
+        assert_eq!(freewheel.fill(3500).len(), 1);
+        assert_eq!(freewheel.fill_before(&real).len(), 1);
+    }
+
+    #[test]
+    fn jam_sync() -> Result<(), Err> {
+        let file = read_test_file()?;
+        let mut decoder = Decoder::new(DecoderOptions::default());
+        let frames = decode_frames(&mut decoder, &file);
+        let first = &frames[0];
+
+        // 3528 samples per frame at 25 fps, the frames of the test file come 3525 samples apart.
+        let mut jam_sync = JamSync::new(88200);
+        let events = jam_sync.process(&frames[0..3], first.position);
+        assert_eq!(events, vec![JamSyncEvent::Jammed(first.clone())]);
+        assert_eq!(jam_sync.drift(), Some(frames[2].position as i64 - (first.position + 2 * 3528) as i64));
+        assert!(jam_sync.drift().unwrap() < 0);
+
+        // Keeps running without any incoming timecode
+        let events = jam_sync.process(&[], first.position + 3528 * 10);
+        assert_eq!(events.len(), 10);
+        assert!(matches!(&events[9], JamSyncEvent::Frame(frame)
+            if frame.position == first.position + 3528 * 10
+            && frame.frame.timecode() == first.frame.timecode().offset(10, 25)));
+
+        // Source comes back, ahead of the clock by the 3 samples per frame it runs faster
+        let late = frames.last().unwrap().clone();
+        let events = jam_sync.process(std::slice::from_ref(&late), late.position);
+        let drift = late.position as i64 - (first.position + 27 * 3528) as i64;
+        assert!((-27 * 3 - 10..-27 * 3 + 10).contains(&drift));
+        assert!(events.contains(&JamSyncEvent::Drift { position: late.position, samples: drift }));
+
+        // Timecode that wraps at midnight keeps running on time
+        let mut jam_sync = JamSync::new(88200);
+        jam_sync.process(&[frame_at(Timecode::new(23, 59, 59, 20, false), 0)], 0);
+        let after = frame_at(Timecode::new(0, 0, 0, 5, false), 3528 * 10);
+        let events = jam_sync.process(std::slice::from_ref(&after), after.position);
+        assert_eq!(jam_sync.drift(), Some(0));
+        assert!(events.contains(&JamSyncEvent::Drift { position: after.position, samples: 0 }));
+        Ok(())
+    }
+
+    #[test]
+    fn continuity() -> Result<(), Err> {
+        let file = read_test_file()?;
+        let mut decoder = Decoder::new(DecoderOptions::default());
+        let frames = decode_frames(&mut decoder, &file);
+        let period = frames[1].position - frames[0].position;
+
+        // Lose two frames, then repeat one, then jump back by a second
+        let mut frames: Vec<DecodedFrame> = frames[0..3].iter().chain(&frames[5..7]).cloned().collect();
+        frames.push(frames[4].clone());
+        let mut jump = frames[5].clone();
+        jump.position += period;
+        jump.frame = jump.frame.interpolate(jump.frame.timecode().offset(-24, 25));
+        frames.push(jump);
+
+        let mut detector = crate::ContinuityDetector::new();
+        let continuity: Vec<Continuity> = frames.iter().map(|frame| detector.classify(frame)).collect();
+        assert_eq!(continuity, vec![
+            Continuity::Continuous,
+            Continuity::Continuous,
+            Continuity::Continuous,
+            Continuity::Skipped(2),
+            Continuity::Continuous,
+            Continuity::Repeated,
+            Continuity::Jumped(-25),
+        ]);
+        Ok(())
+    }
+
+    #[test]
+    fn signal_lost() -> Result<(), Err> {
+        let file = read_test_file()?;
+        let mut decoder = Decoder::new(DecoderOptions { signal_timeout: Some(10000), ..DecoderOptions::default() });
+        let mut events = decoder.decode(&file);
+        events.extend(decoder.decode(&[0.0; 20000]));
+        for chunk in file.chunks(8192) {
+            events.extend(decoder.decode(chunk));
+        }
+        let frames: Vec<u64> = events.iter().filter_map(|event| event.frame()).map(|frame| frame.position).collect();
+        let signal: Vec<&LtcEvent> = events.iter()
+            .filter(|event| matches!(event, LtcEvent::Locked { .. } | LtcEvent::SignalLost { .. }))
+            .collect();
+        let last = frames.iter().filter(|&&position| position < file.len() as u64).max().unwrap();
+        assert!(matches!(signal.as_slice(), [
+            LtcEvent::Locked { position: first },
+            LtcEvent::SignalLost { position: lost },
+            LtcEvent::Locked { .. },
+        ] if *first == frames[0] && *lost == last + 10000));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() -> Result<(), Err> {
+        let file = read_test_file()?;
+        let mut decoder = Decoder::new(DecoderOptions::default());
+        let events = decoder.decode(&file);
+        let json = serde_json::to_value(&events)?;
+        assert_eq!(json[0]["type"], "locked");
+        assert_eq!(json[1]["type"], "frame");
+        assert_eq!(json[1]["frame"]["hour"], 19);
+        assert_eq!(json[1]["frame"]["drop"], false);
+        assert_eq!(serde_json::from_value::<Vec<LtcEvent>>(json)?, events);
+        Ok(())
+    }
+}
@@ -1,16 +1,30 @@
-use std::fmt::Formatter;
+use core::fmt::Formatter;
 
+use crate::timecode::Timecode;
+use crate::userdata::Userdata;
+
+/// A single LTC frame.
+///
+/// Serialized as an object with the fields `hour`, `minute`, `second`, `frame`, the flags
+/// `drop`, `color`, `clock`, `interpolated` and `parity`, and the raw `userdata_format` (3
+/// bits) and `userdata` (32 bits) as arrays of booleans in transmission order.
 #[derive(Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LtcFrame {
     frame: u8,
     second: u8,
     minute: u8,
     hour: u8,
+    #[cfg_attr(feature = "serde", serde(rename = "drop"))]
     flag_drop: bool,
+    #[cfg_attr(feature = "serde", serde(rename = "color"))]
     flag_color: bool,
+    #[cfg_attr(feature = "serde", serde(rename = "clock"))]
     flag_clock: bool,
-    userdata_format: Vec<bool>,
-    userdata: Vec<bool>,
+    userdata_format: [bool; 3],
+    userdata: [bool; 32],
+    interpolated: bool,
+    parity: bool,
 }
 
 
@@ -45,16 +59,11 @@ impl LtcFrame {
         let flag_drop = data[10];
         let flag_color = data[11];
         let flag_clock = data[58];
-        let userdata_format = vec![data[27], data[43], data[59]];
-        let mut userdata: Vec<bool> = vec![];
-        userdata.extend_from_slice(&data[4..8]);
-        userdata.extend_from_slice(&data[12..16]);
-        userdata.extend_from_slice(&data[20..24]);
-        userdata.extend_from_slice(&data[28..32]);
-        userdata.extend_from_slice(&data[36..40]);
-        userdata.extend_from_slice(&data[44..48]);
-        userdata.extend_from_slice(&data[52..56]);
-        userdata.extend_from_slice(&data[60..64]);
+        let userdata_format = [data[27], data[43], data[59]];
+        let mut userdata = [false; 32];
+        for (group, bits) in userdata.chunks_mut(4).enumerate() {
+            bits.copy_from_slice(&data[group * 8 + 4..group * 8 + 8]);
+        }
         return LtcFrame {
             frame,
             second,
@@ -65,9 +74,24 @@ impl LtcFrame {
             flag_clock,
             userdata_format,
             userdata,
+            interpolated: false,
+            parity: data.iter().filter(|&&bit| bit).count() % 2 == 0,
         };
     }
 
+    /// Creates a frame that was not decoded from the signal, but generated from this one to
+    /// stand in for the frame at `timecode`. Flags and userdata are carried over.
+    pub fn interpolate(&self, timecode: Timecode) -> LtcFrame {
+        LtcFrame {
+            frame: timecode.frame(),
+            second: timecode.second(),
+            minute: timecode.minute(),
+            hour: timecode.hour(),
+            interpolated: true,
+            ..self.clone()
+        }
+    }
+
     pub fn frame(&self) -> u8 {
         self.frame
     }
@@ -89,55 +113,91 @@ impl LtcFrame {
     pub fn is_clock(&self) -> bool {
         self.flag_clock
     }
+    pub fn is_interpolated(&self) -> bool {
+        self.interpolated
+    }
+    /// Whether the frame has an even number of ones, as the biphase mark correction bit should
+    /// ensure. Many generators do not set that bit, so this is only meaningful for sources that
+    /// are known to.
+    pub fn has_valid_parity(&self) -> bool {
+        self.parity
+    }
+    pub fn timecode(&self) -> Timecode {
+        Timecode::new(self.hour, self.minute, self.second, self.frame, self.flag_drop)
+    }
     pub fn userdata_format(&self) -> &[bool] {
-        self.userdata_format.as_slice()
+        &self.userdata_format
     }
     pub fn userdata(&self) -> &[bool] {
-        self.userdata.as_slice()
+        &self.userdata
+    }
+
+    /// Decodes the userdata according to the binary group flags. Where these flags sit in the
+    /// frame depends on the frame rate: 25 fps timecode uses a different layout from all other
+    /// rates.
+    pub fn decode_userdata(&self, fps: u128) -> Userdata {
+        let mut groups = [0u8; 8];
+        for (group, bits) in groups.iter_mut().zip(self.userdata.chunks(4)) {
+            *group = read_digit(bits);
+        }
+        let (bgf0, bgf2) = if fps == 25 {
+            (self.userdata_format[0], self.userdata_format[1])
+        } else {
+            (self.userdata_format[1], self.userdata_format[2])
+        };
+        Userdata::decode(groups, bgf0, bgf2)
+    }
+
+    /// The calendar date and time of this frame, if its userdata carries an SMPTE 309M date and
+    /// time zone.
+    #[cfg(feature = "chrono")]
+    pub fn date_time(&self, fps: u128) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+        match self.decode_userdata(fps) {
+            Userdata::DateTimeZone(date) => date.to_date_time(self.timecode(), fps),
+            _ => None,
+        }
     }
 }
 
-impl std::fmt::Display for LtcFrame {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:02}:{:02}:{:02}:{:03}",
-               self.hour(), self.minute(), self.second(), self.frame(),
-        )
+impl core::fmt::Display for LtcFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.timecode())
     }
 }
 
-impl std::fmt::Debug for LtcFrame {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let mut flags = vec![];
-        if self.is_color() {
-            flags.push("color");
-        }
-        if self.is_drop() {
-            flags.push("drop");
-        }
-        if self.is_clock() {
-            flags.push("clock");
-        }
-        let flags = flags.join(",");
-        if f.alternate() {
-            write!(f, "LtcFrame {{\n  timecode={}\n  flags=[{}]\n  userdata_format={}\n  userdata={}\n}}",
-                   self.to_string(),
-                   flags,
-                   format_bits(self.userdata_format()),
-                   format_bits(self.userdata())
-            )
+impl core::fmt::Debug for LtcFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let (start, separator, end) = if f.alternate() {
+            ("LtcFrame {\n  ", "\n  ", "\n}")
         } else {
-            write!(f, "LtcFrame {{ timecode={}, flags=[{}], userdata_format={}, userdata={} }}",
-                   self.to_string(),
-                   flags,
-                   format_bits(self.userdata_format()),
-                   format_bits(self.userdata())
-            )
+            ("LtcFrame { ", ", ", " }")
+        };
+        write!(f, "{}timecode={}{}flags=[", start, self, separator)?;
+        let flags = [
+            (self.is_color(), "color"),
+            (self.is_drop(), "drop"),
+            (self.is_clock(), "clock"),
+            (self.is_interpolated(), "interpolated"),
+        ];
+        let mut first = true;
+        for (_, name) in flags.iter().filter(|(set, _)| *set) {
+            if !first {
+                f.write_str(",")?;
+            }
+            f.write_str(name)?;
+            first = false;
         }
+        write!(f, "]{}userdata_format=", separator)?;
+        write_bits(f, self.userdata_format())?;
+        write!(f, "{}userdata=", separator)?;
+        write_bits(f, self.userdata())?;
+        f.write_str(end)
     }
 }
 
-fn format_bits(data: &[bool]) -> String {
-    return data.iter()
-        .map(|&bit| if bit { "1" } else { "0" })
-        .collect::<Vec<_>>().join("");
+fn write_bits(f: &mut Formatter<'_>, data: &[bool]) -> core::fmt::Result {
+    for &bit in data {
+        f.write_str(if bit { "1" } else { "0" })?;
+    }
+    Ok(())
 }
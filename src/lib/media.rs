@@ -0,0 +1,272 @@
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{self, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as MediaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, Track};
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::error::Error;
+use crate::source::SampleSource;
+
+/// An audio track of a media file.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct MediaTrack {
+    /// Short name of the codec, e.g. `flac` or `pcm_s24le`.
+    pub codec: &'static str,
+    /// Number of channels, if the container tells.
+    pub channels: Option<u16>,
+    pub sample_rate: u32,
+}
+
+/// Reads samples from compressed or container audio, like FLAC, MP3, or the audio tracks of
+/// MOV and MP4 files.
+///
+/// One channel of one audio track is read at a time, see [`set_track`](Self::set_track) and
+/// [`set_channel`](Self::set_channel). Both count from 0, and only audio tracks are counted.
+pub struct MediaReader {
+    format: Box<dyn FormatReader>,
+    /// Indices of the audio tracks in `format.tracks()`.
+    audio_tracks: Vec<usize>,
+    track: usize,
+    decoder: Box<dyn codecs::Decoder>,
+    channel: u16,
+    samples: Vec<f32>,
+    offset: usize,
+}
+
+impl MediaReader {
+    /// Opens a media file and selects its first audio track.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        MediaReader::new(File::open(path)?, path.extension().and_then(|it| it.to_str()))
+    }
+
+    /// Reads the headers of the media in `source` and selects its first audio track. The file
+    /// name `extension`, if known, helps to tell the format.
+    pub fn new(source: impl MediaSource + 'static, extension: Option<&str>) -> Result<Self, Error> {
+        let source = MediaSourceStream::new(Box::new(source), Default::default());
+        let mut hint = Hint::new();
+        if let Some(extension) = extension {
+            hint.with_extension(extension);
+        }
+        let options = FormatOptions { enable_gapless: true, ..FormatOptions::default() };
+        let format = symphonia::default::get_probe()
+            .format(&hint, source, &options, &MetadataOptions::default())?
+            .format;
+        let audio_tracks: Vec<usize> = format.tracks().iter().enumerate()
+            .filter(|(_, track)| track.codec_params.codec != CODEC_TYPE_NULL)
+            .map(|(index, _)| index)
+            .collect();
+        let first = *audio_tracks.first()
+            .ok_or_else(|| Error::InvalidFile("no audio track".to_string()))?;
+        let decoder = make_decoder(&format.tracks()[first])?;
+        Ok(MediaReader {
+            format,
+            audio_tracks,
+            track: 0,
+            decoder,
+            channel: 0,
+            samples: vec![],
+            offset: 0,
+        })
+    }
+
+    pub fn tracks(&self) -> Vec<MediaTrack> {
+        self.audio_tracks.iter().map(|&index| {
+            let params = &self.format.tracks()[index].codec_params;
+            MediaTrack {
+                codec: symphonia::default::get_codecs().get_codec(params.codec)
+                    .map(|it| it.short_name)
+                    .unwrap_or("unknown"),
+                channels: params.channels.map(|it| it.count() as u16),
+                sample_rate: params.sample_rate.unwrap_or(0),
+            }
+        }).collect()
+    }
+
+    pub fn track(&self) -> usize {
+        self.track
+    }
+
+    /// Selects the audio track to read. Only works before any samples were read: all tracks are
+    /// read from the same position in the file, so a track selected later starts where the
+    /// previous one stopped.
+    pub fn set_track(&mut self, track: usize) -> Result<(), Error> {
+        let &index = self.audio_tracks.get(track)
+            .ok_or(Error::NoTrack { track, tracks: self.audio_tracks.len() })?;
+        self.decoder = make_decoder(&self.format.tracks()[index])?;
+        self.track = track;
+        Ok(())
+    }
+
+    pub fn channel(&self) -> u16 {
+        self.channel
+    }
+
+    /// Selects the channel to read, counting from 0.
+    pub fn set_channel(&mut self, channel: u16) -> Result<(), Error> {
+        match self.tracks()[self.track].channels {
+            Some(channels) if channel >= channels => Err(Error::NoChannel { channel, channels }),
+            _ => {
+                self.channel = channel;
+                Ok(())
+            }
+        }
+    }
+
+    /// Decodes the next packet of the selected track into `samples`. Returns false at the end.
+    fn decode_packet(&mut self) -> Result<bool, Error> {
+        let track_id = self.format.tracks()[self.audio_tracks[self.track]].id;
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(MediaError::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => return Ok(false),
+                Err(MediaError::ResetRequired) => return Ok(false),
+                Err(err) => return Err(err.into()),
+            };
+            if packet.track_id() != track_id {
+                continue;
+            }
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // A damaged packet, the next one may decode fine.
+                Err(MediaError::DecodeError(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            let spec = *decoded.spec();
+            let channels = spec.channels.count() as u16;
+            if self.channel >= channels {
+                return Err(Error::NoChannel { channel: self.channel, channels });
+            }
+            let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+            buffer.copy_interleaved_ref(decoded);
+            self.samples.clear();
+            self.samples.extend(buffer.samples().iter()
+                .skip(self.channel as usize)
+                .step_by(channels as usize));
+            self.offset = 0;
+            return Ok(true);
+        }
+    }
+}
+
+impl SampleSource for MediaReader {
+    fn sample_rate(&self) -> u32 {
+        self.tracks()[self.track].sample_rate
+    }
+
+    fn read(&mut self, buffer: &mut Vec<f32>, samples: usize) -> Result<usize, Error> {
+        let mut count = 0;
+        while count < samples {
+            if self.offset == self.samples.len() && !self.decode_packet()? {
+                break;
+            }
+            let available = &self.samples[self.offset..];
+            let take = available.len().min(samples - count);
+            buffer.extend_from_slice(&available[..take]);
+            self.offset += take;
+            count += take;
+        }
+        Ok(count)
+    }
+}
+
+fn make_decoder(track: &Track) -> Result<Box<dyn codecs::Decoder>, Error> {
+    Ok(symphonia::default::get_codecs().make(&track.codec_params, &codecs::DecoderOptions::default())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::tests::{read_test_file, source_frames, test_source, Err};
+    use crate::{Error, MediaReader, MediaTrack};
+
+    fn crc8(data: &[u8]) -> u8 {
+        data.iter().fold(0u8, |crc, &byte| {
+            (0..8).fold(crc ^ byte, |crc, _| if crc & 0x80 != 0 { crc << 1 ^ 0x07 } else { crc << 1 })
+        })
+    }
+
+    fn crc16(data: &[u8]) -> u16 {
+        data.iter().fold(0u16, |crc, &byte| {
+            (0..8).fold(crc ^ (byte as u16) << 8, |crc, _| if crc & 0x8000 != 0 { crc << 1 ^ 0x8005 } else { crc << 1 })
+        })
+    }
+
+    /// A 16 bit stereo FLAC file with silence on the first channel and `samples` on the second,
+    /// stored in verbatim subframes of 4096 samples.
+    fn flac_file(sample_rate: u32, samples: &[f32]) -> Vec<u8> {
+        const BLOCK: usize = 4096;
+        let mut file = b"fLaC".to_vec();
+        // The last metadata block, STREAMINFO, with unknown frame sizes and MD5 sum.
+        file.extend([0x80, 0, 0, 34]);
+        file.extend((BLOCK as u16).to_be_bytes());
+        file.extend((BLOCK as u16).to_be_bytes());
+        file.extend([0; 6]);
+        let info = (sample_rate as u64) << 44 | 1 << 41 | 15 << 36 | samples.len() as u64;
+        file.extend(info.to_be_bytes());
+        file.extend([0; 16]);
+
+        for (index, block) in samples.chunks(BLOCK).enumerate() {
+            // Fixed block size given at the end of the header, sample rate from STREAMINFO, two
+            // independent channels of 16 bits. Frame numbers below 128 take one byte.
+            let mut frame = vec![0xFF, 0xF8, 0x70, 0x18, index as u8];
+            frame.extend((block.len() as u16 - 1).to_be_bytes());
+            frame.push(crc8(&frame));
+            for channel in 0..2 {
+                frame.push(0x02);
+                for &sample in block {
+                    let value = if channel == 0 { 0 } else { (sample * 32767.0) as i16 };
+                    frame.extend(value.to_be_bytes());
+                }
+            }
+            frame.extend(crc16(&frame).to_be_bytes());
+            file.extend(frame);
+        }
+        file
+    }
+
+    #[test]
+    fn decode() -> Result<(), Err> {
+        let samples = read_test_file()?;
+
+        // Stereo 32 bit float WAV, with the LTC on the second channel.
+        let mut data = vec![];
+        for &sample in &samples {
+            data.extend(0f32.to_le_bytes());
+            data.extend(sample.to_le_bytes());
+        }
+        let mut file = b"RIFF".to_vec();
+        file.extend((36 + data.len() as u32).to_le_bytes());
+        file.extend(b"WAVEfmt \x10\0\0\0\x03\0\x02\0\x80\xbb\0\0\0\xdc\x05\0\x08\0\x20\0data");
+        file.extend((data.len() as u32).to_le_bytes());
+        file.extend(data);
+
+        let mut reader = MediaReader::new(Cursor::new(file), Some("wav"))?;
+        assert_eq!(reader.tracks().len(), 1);
+        assert_eq!(reader.tracks()[0].channels, Some(2));
+        assert!(matches!(reader.set_track(1), Err(Error::NoTrack { track: 1, tracks: 1 })));
+        assert!(matches!(reader.set_channel(2), Err(Error::NoChannel { channel: 2, channels: 2 })));
+        reader.set_channel(1)?;
+        assert_eq!(source_frames(reader)?, source_frames(test_source(&samples))?);
+        Ok(())
+    }
+
+    #[test]
+    fn flac() -> Result<(), Err> {
+        let samples = read_test_file()?;
+        let mut reader = MediaReader::new(Cursor::new(flac_file(88200, &samples)), Some("flac"))?;
+        assert_eq!(reader.tracks(), vec![MediaTrack { codec: "flac", channels: Some(2), sample_rate: 88200 }]);
+        assert!(matches!(reader.set_track(1), Err(Error::NoTrack { track: 1, tracks: 1 })));
+        reader.set_track(0)?;
+        reader.set_channel(1)?;
+        assert_eq!(source_frames(reader)?, source_frames(test_source(&samples))?);
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+use std::thread;
+
+use crate::decoder::{Decoder, DecoderOptions};
+use crate::error::Error;
+use crate::event::LtcEvent;
+use crate::source::{SampleSource, SourceEvents};
+
+/// Length of the chunks decoded in parallel, in blocks of one second.
+const CHUNK_BLOCKS: usize = 20;
+/// Number of blocks before a chunk that are decoded to bring the decoder into the state the
+/// sequential decoder has at the start of the chunk.
+const WARMUP_BLOCKS: usize = 2;
+
+/// Iterator over the events decoded from a [`SampleSource`] on several threads, see
+/// [`decode_source_parallel`].
+pub struct ParallelSourceDecoder<S> {
+    source: S,
+    options: DecoderOptions,
+    threads: usize,
+    chunk_blocks: usize,
+    /// State after everything decoded so far.
+    decoder: Decoder,
+    biphase_errors: u64,
+    /// The last blocks read, to warm up the decoders of the next chunks.
+    tail: Vec<f32>,
+    pending: VecDeque<LtcEvent>,
+    done: bool,
+    /// Number of chunks that had to be decoded again sequentially.
+    redecoded: u64,
+}
+
+/// Decodes all of `source` like [`decode_source`](crate::decode_source), with the same events,
+/// but on up to `threads` threads, or as many as there are cores if `threads` is 0.
+///
+/// The source is read in chunks of 20 seconds, and every chunk is decoded by its own decoder,
+/// which first decodes the two seconds before the chunk. The events of a chunk are only used
+/// if that brought its decoder into exactly the state that the decoder of the previous chunk
+/// ended in, otherwise the chunk is decoded again with the decoder of the previous chunk.
+/// Either way the events are the ones the sequential decoder would have returned, as long as
+/// `source` fills every read, and every chunk costs 10% more work in the usual case.
+pub fn decode_source_parallel<S: SampleSource>(source: S, options: DecoderOptions, threads: usize) -> ParallelSourceDecoder<S> {
+    let threads = match threads {
+        0 => thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1),
+        threads => threads,
+    };
+    ParallelSourceDecoder::new(source, options, threads, CHUNK_BLOCKS)
+}
+
+/// The result of decoding one chunk.
+struct DecodedChunk {
+    /// State of the decoder before the chunk, `None` if it continued the sequential state.
+    start: Option<Decoder>,
+    end: Decoder,
+    events: Vec<LtcEvent>,
+}
+
+impl<S: SampleSource> ParallelSourceDecoder<S> {
+    fn new(source: S, options: DecoderOptions, threads: usize, chunk_blocks: usize) -> Self {
+        ParallelSourceDecoder {
+            source,
+            decoder: Decoder::new(options.clone()),
+            options,
+            threads,
+            chunk_blocks,
+            biphase_errors: 0,
+            tail: vec![],
+            pending: VecDeque::new(),
+            done: false,
+            redecoded: 0,
+        }
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Number of samples decoded so far.
+    pub fn position(&self) -> u64 {
+        self.decoder.position()
+    }
+
+    /// Number of biphase mark coding violations in the signal so far.
+    pub fn biphase_errors(&self) -> u64 {
+        self.biphase_errors
+    }
+
+    /// Reads the next chunks for all threads, and decodes them.
+    fn decode_next(&mut self) -> Result<(), Error> {
+        let block = self.source.sample_rate().max(1) as usize;
+        let chunk_length = block * self.chunk_blocks;
+        let length = chunk_length * self.threads;
+        let mut data = std::mem::take(&mut self.tail);
+        let offset = data.len();
+        while data.len() < offset + length {
+            let missing = offset + length - data.len();
+            if self.source.read(&mut data, missing)? == 0 {
+                self.done = true;
+                break;
+            }
+        }
+        let start = self.decoder.position();
+        let warmup = block * WARMUP_BLOCKS;
+        self.tail = data[data.len().saturating_sub(warmup)..].to_vec();
+
+        let options = &self.options;
+        let decoder = self.decoder.clone();
+        let chunks: Vec<DecodedChunk> = thread::scope(|scope| {
+            let handles: Vec<_> = data[offset..].chunks(chunk_length).enumerate().map(|(index, chunk)| {
+                let chunk_start = offset + index * chunk_length;
+                let warmup = &data[chunk_start.saturating_sub(warmup)..chunk_start];
+                let decoder = (index == 0).then(|| decoder.clone());
+                scope.spawn(move || {
+                    let (start, mut decoder) = match decoder {
+                        Some(decoder) => (None, decoder),
+                        None => {
+                            let position = start + (index * chunk_length) as u64 - warmup.len() as u64;
+                            let mut decoder = Decoder::starting_at(options.clone(), position);
+                            for block in warmup.chunks(block) {
+                                decoder.decode(block);
+                            }
+                            (Some(decoder.clone()), decoder)
+                        }
+                    };
+                    let events = chunk.chunks(block).flat_map(|block| decoder.decode(block)).collect();
+                    DecodedChunk { start, end: decoder, events }
+                })
+            }).collect();
+            handles.into_iter().map(|handle| handle.join().expect("decoder thread panicked")).collect()
+        });
+
+        let mut position = offset;
+        for chunk in chunks {
+            let samples = &data[position..(position + chunk_length).min(data.len())];
+            position += samples.len();
+            let errors_before = match &chunk.start {
+                None => self.decoder.biphase_errors(),
+                Some(state) if state.same_state(&self.decoder) => state.biphase_errors(),
+                Some(_) => {
+                    // The warm-up did not catch up with the previous chunk, continue from there.
+                    self.redecoded += 1;
+                    let errors_before = self.decoder.biphase_errors();
+                    for block in samples.chunks(block) {
+                        self.pending.extend(self.decoder.decode(block));
+                    }
+                    self.biphase_errors += self.decoder.biphase_errors() - errors_before;
+                    continue;
+                }
+            };
+            self.biphase_errors += chunk.end.biphase_errors() - errors_before;
+            self.pending.extend(chunk.events);
+            self.decoder = chunk.end;
+        }
+        Ok(())
+    }
+}
+
+impl<S: SampleSource> Iterator for ParallelSourceDecoder<S> {
+    type Item = Result<LtcEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() && !self.done {
+            if let Err(err) = self.decode_next() {
+                self.done = true;
+                return Some(Err(err));
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+impl<S: SampleSource> SourceEvents for ParallelSourceDecoder<S> {
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn position(&self) -> u64 {
+        self.decoder.position()
+    }
+
+    fn biphase_errors(&self) -> u64 {
+        self.biphase_errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{read_test_file, test_source, Err};
+    use crate::{decode_source, DecoderOptions};
+
+    use super::ParallelSourceDecoder;
+
+    #[test]
+    fn same_as_sequential() -> Result<(), Err> {
+        // Copies of the recording with jumps back in the timecode, silence and noise in between.
+        let samples = read_test_file()?;
+        let mut data = vec![];
+        for copy in 0..12 {
+            data.extend(&samples);
+            match copy % 4 {
+                1 => data.extend(vec![0.0; 30000]),
+                2 => data.extend((0..20000).map(|it| ((it * 7919 % 2000) as f32 / 1000.0) - 1.0)),
+                _ => {}
+            }
+        }
+        data.extend(&samples[..50000]);
+        let raw = || test_source(&data);
+
+        for options in [
+            DecoderOptions::default(),
+            DecoderOptions { freewheel_frames: 5, signal_timeout: Some(44100) },
+        ] {
+            let mut sequential = decode_source(raw(), options.clone());
+            let expected = sequential.by_ref().collect::<Result<Vec<_>, _>>()?;
+            assert!(expected.len() > 200);
+            for (threads, chunk_blocks) in [(1, 1), (4, 1), (3, 2), (2, 20)] {
+                let mut parallel = ParallelSourceDecoder::new(raw(), options.clone(), threads, chunk_blocks);
+                let events = parallel.by_ref().collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(events, expected, "{} threads, {} blocks per chunk", threads, chunk_blocks);
+                assert_eq!(parallel.position(), sequential.position());
+                assert_eq!(parallel.biphase_errors(), sequential.decoder().biphase_errors());
+                // Most chunks need to be decoded only once, even with the shortest chunks.
+                if chunk_blocks == 1 {
+                    assert!(parallel.redecoded < data.len() as u64 / 88200 / 2, "{} chunks decoded again", parallel.redecoded);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,232 @@
+use std::io::{ErrorKind, Read};
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::source::SampleSource;
+
+/// Encoding of raw PCM samples, named like the sample formats of `ffmpeg` and `sox`.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub enum PcmFormat {
+    U8,
+    S8,
+    S16Le,
+    S16Be,
+    S24Le,
+    S24Be,
+    S32Le,
+    S32Be,
+    F32Le,
+    F32Be,
+    F64Le,
+    F64Be,
+}
+
+impl PcmFormat {
+    /// The little endian format WAV files use for samples of this size.
+    pub(crate) fn wav(bits_per_sample: u16, float: bool) -> Option<PcmFormat> {
+        match (float, bits_per_sample) {
+            (false, 8) => Some(PcmFormat::U8),
+            (false, 16) => Some(PcmFormat::S16Le),
+            (false, 24) => Some(PcmFormat::S24Le),
+            (false, 32) => Some(PcmFormat::S32Le),
+            (true, 32) => Some(PcmFormat::F32Le),
+            (true, 64) => Some(PcmFormat::F64Le),
+            _ => None,
+        }
+    }
+
+    pub fn bytes_per_sample(&self) -> usize {
+        match self {
+            PcmFormat::U8 | PcmFormat::S8 => 1,
+            PcmFormat::S16Le | PcmFormat::S16Be => 2,
+            PcmFormat::S24Le | PcmFormat::S24Be => 3,
+            PcmFormat::S32Le | PcmFormat::S32Be | PcmFormat::F32Le | PcmFormat::F32Be => 4,
+            PcmFormat::F64Le | PcmFormat::F64Be => 8,
+        }
+    }
+
+    /// Reads one sample from the start of `data`.
+    pub fn decode(&self, data: &[u8]) -> f32 {
+        match self {
+            PcmFormat::U8 => (data[0] as f32 - 128.0) / 128.0,
+            PcmFormat::S8 => data[0] as i8 as f32 / 128.0,
+            PcmFormat::S16Le => i16::from_le_bytes([data[0], data[1]]) as f32 / 32768.0,
+            PcmFormat::S16Be => i16::from_be_bytes([data[0], data[1]]) as f32 / 32768.0,
+            PcmFormat::S24Le => (i32::from_le_bytes([0, data[0], data[1], data[2]]) >> 8) as f32 / 8388608.0,
+            PcmFormat::S24Be => (i32::from_be_bytes([data[0], data[1], data[2], 0]) >> 8) as f32 / 8388608.0,
+            PcmFormat::S32Le => (i32::from_le_bytes(data[0..4].try_into().unwrap()) as f64 / 2147483648.0) as f32,
+            PcmFormat::S32Be => (i32::from_be_bytes(data[0..4].try_into().unwrap()) as f64 / 2147483648.0) as f32,
+            PcmFormat::F32Le => f32::from_le_bytes(data[0..4].try_into().unwrap()),
+            PcmFormat::F32Be => f32::from_be_bytes(data[0..4].try_into().unwrap()),
+            PcmFormat::F64Le => f64::from_le_bytes(data[0..8].try_into().unwrap()) as f32,
+            PcmFormat::F64Be => f64::from_be_bytes(data[0..8].try_into().unwrap()) as f32,
+        }
+    }
+}
+
+impl FromStr for PcmFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "u8" => Ok(PcmFormat::U8),
+            "s8" => Ok(PcmFormat::S8),
+            "s16le" => Ok(PcmFormat::S16Le),
+            "s16be" => Ok(PcmFormat::S16Be),
+            "s24le" => Ok(PcmFormat::S24Le),
+            "s24be" => Ok(PcmFormat::S24Be),
+            "s32le" => Ok(PcmFormat::S32Le),
+            "s32be" => Ok(PcmFormat::S32Be),
+            "f32le" => Ok(PcmFormat::F32Le),
+            "f32be" => Ok(PcmFormat::F32Be),
+            "f64le" => Ok(PcmFormat::F64Le),
+            "f64be" => Ok(PcmFormat::F64Be),
+            other => Err(format!("unknown sample format \"{}\", expected u8, s8, s16le, s16be, s24le, s24be, s32le, s32be, f32le, f32be, f64le or f64be", other)),
+        }
+    }
+}
+
+/// Reads interleaved raw PCM samples without any header, like `ffmpeg -f s16le` or `arecord -t
+/// raw` write them, from a pipe, a file or anything else that implements [`Read`].
+pub struct RawReader<R> {
+    reader: R,
+    format: PcmFormat,
+    sample_rate: u32,
+    channels: u16,
+    channel: u16,
+    bytes: Vec<u8>,
+}
+
+impl<R: Read> RawReader<R> {
+    pub fn new(reader: R, format: PcmFormat, sample_rate: u32, channels: u16) -> RawReader<R> {
+        RawReader {
+            reader,
+            format,
+            sample_rate,
+            channels: channels.max(1),
+            channel: 0,
+            bytes: vec![],
+        }
+    }
+
+    pub fn channel(&self) -> u16 {
+        self.channel
+    }
+
+    /// Selects the channel to read, counting from 0.
+    pub fn set_channel(&mut self, channel: u16) -> Result<(), Error> {
+        if channel >= self.channels {
+            return Err(Error::NoChannel { channel, channels: self.channels });
+        }
+        self.channel = channel;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> SampleSource for RawReader<R> {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn read(&mut self, buffer: &mut Vec<f32>, samples: usize) -> Result<usize, Error> {
+        let block_align = self.format.bytes_per_sample() * self.channels as usize;
+        self.bytes.resize(samples * block_align, 0);
+        // Pipes deliver data in pieces, wait until the block is full or the input ends.
+        let mut length = 0;
+        while length < self.bytes.len() {
+            match self.reader.read(&mut self.bytes[length..]) {
+                Ok(0) => break,
+                Ok(count) => length += count,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let offset = self.channel as usize * self.format.bytes_per_sample();
+        let before = buffer.len();
+        buffer.extend(self.bytes[..length].chunks_exact(block_align)
+            .map(|frame| self.format.decode(&frame[offset..])));
+        Ok(buffer.len() - before)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, ErrorKind, Read};
+
+    use crate::{PcmFormat, RawReader, SampleSource};
+
+    /// Delivers at most three bytes per read, and is interrupted before every other read, like
+    /// a slow pipe.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        interrupt: bool,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(ErrorKind::Interrupted.into());
+            }
+            let count = buf.len().min(self.data.len()).min(3);
+            buf[..count].copy_from_slice(&self.data[..count]);
+            self.data = &self.data[count..];
+            Ok(count)
+        }
+    }
+
+    #[test]
+    fn decode() {
+        assert_eq!(PcmFormat::U8.decode(&[0x40]), -0.5);
+        assert_eq!(PcmFormat::S8.decode(&[0xC0]), -0.5);
+        assert_eq!(PcmFormat::S16Le.decode(&[0x00, 0x40]), 0.5);
+        assert_eq!(PcmFormat::S16Be.decode(&[0xC0, 0x00]), -0.5);
+        assert_eq!(PcmFormat::S24Le.decode(&[0x00, 0x00, 0xC0]), -0.5);
+        assert_eq!(PcmFormat::S24Be.decode(&[0x40, 0x00, 0x00]), 0.5);
+        assert_eq!(PcmFormat::S32Le.decode(&[0x00, 0x00, 0x00, 0x40]), 0.5);
+        assert_eq!(PcmFormat::S32Be.decode(&[0xC0, 0x00, 0x00, 0x00]), -0.5);
+        assert_eq!(PcmFormat::F32Le.decode(&0.25f32.to_le_bytes()), 0.25);
+        assert_eq!(PcmFormat::F32Be.decode(&0.25f32.to_be_bytes()), 0.25);
+        assert_eq!(PcmFormat::F64Le.decode(&0.25f64.to_le_bytes()), 0.25);
+        assert_eq!(PcmFormat::F64Be.decode(&0.25f64.to_be_bytes()), 0.25);
+        assert_eq!("s24le".parse(), Ok(PcmFormat::S24Le));
+        assert!("s12le".parse::<PcmFormat>().is_err());
+    }
+
+    #[test]
+    fn read() {
+        // Two interleaved s16le channels, the second one at half the level of the first, and
+        // half a frame at the end.
+        let mut data = vec![];
+        for sample in 0..100i16 {
+            data.extend((sample * 200).to_le_bytes());
+            data.extend((sample * 100).to_le_bytes());
+        }
+        data.extend([0x00, 0x40]);
+
+        for channel in 0..2 {
+            let mut reader = RawReader::new(Trickle { data: &data, interrupt: false }, PcmFormat::S16Le, 48000, 2);
+            reader.set_channel(channel).unwrap();
+            let mut buffer = vec![];
+            let mut counts = vec![];
+            loop {
+                match reader.read(&mut buffer, 40).unwrap() {
+                    0 => break,
+                    count => counts.push(count),
+                }
+            }
+            assert_eq!(counts, [40, 40, 20]);
+            let expected: Vec<f32> = (0..100)
+                .map(|sample| (sample * if channel == 0 { 200 } else { 100 }) as f32 / 32768.0)
+                .collect();
+            assert_eq!(buffer, expected);
+        }
+        let mut reader = RawReader::new(data.as_slice(), PcmFormat::S16Le, 48000, 2);
+        assert!(reader.set_channel(2).is_err());
+    }
+}
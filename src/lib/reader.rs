@@ -1,14 +1,22 @@
 use crate::ltc_frame::LtcFrame;
 
-const LTC_FRAME_HEADER: &[bool] = &[
-    false, false, true, true,
-    true, true, true, true,
-    true, true, true, true,
-    true, true, false, true
-];
+/// The sync word at the end of every frame, in the order the bits arrive.
+const LTC_FRAME_HEADER: u128 = 0b0011_1111_1111_1101;
+const LTC_FRAME_HEADER_MASK: u128 = 0xFFFF;
+const LTC_FRAME_LENGTH: usize = 80;
 
+#[derive(Clone, PartialEq, Eq)]
 pub struct LtcFrameReader {
-    buffer: Vec<bool>,
+    /// The last 80 bits, the latest one in the lowest bit.
+    buffer: u128,
+    /// Number of bits since the last sync word.
+    buffer_length: usize,
+    buffer_position: u64,
+    /// Sample position at which the first bit since the last sync word ended.
+    first_position: u64,
+    /// Longest time between two bits since the first one.
+    longest_bit: u64,
+    last_position: u64,
     last_frame_count: u128,
     frame_rate: Option<u128>,
 }
@@ -16,7 +24,12 @@ pub struct LtcFrameReader {
 impl LtcFrameReader {
     pub fn new() -> LtcFrameReader {
         return LtcFrameReader {
-            buffer: vec![],
+            buffer: 0,
+            buffer_length: 0,
+            buffer_position: 0,
+            first_position: 0,
+            longest_bit: 0,
+            last_position: 0,
             last_frame_count: 0,
             frame_rate: None,
         };
@@ -26,27 +39,68 @@ impl LtcFrameReader {
         return self.frame_rate;
     }
 
+    #[cfg(feature = "std")]
     pub fn read(&mut self, data: &Vec<bool>) -> Vec<LtcFrame> {
-        return data.into_iter().filter_map(|&bit| {
-            let mut result: Option<LtcFrame> = None;
-            if self.buffer.ends_with(LTC_FRAME_HEADER) {
-                if self.buffer.len() == 80 {
-                    let frame = LtcFrame::read(self.buffer.as_slice());
-                    let frame_id = frame.frame() as u128;
-                    if frame_id < self.last_frame_count {
-                        self.frame_rate = Some(self.last_frame_count + 1);
-                        self.last_frame_count = frame_id;
-                    }
+        data.iter()
+            .filter_map(|&bit| self.push(0, bit))
+            .map(|(_, frame)| frame)
+            .collect()
+    }
+
+    /// Like [`read`](Self::read), but every bit is paired with the sample position at which it
+    /// ended. Each frame is returned together with the sample position at which it started.
+    #[cfg(feature = "std")]
+    pub fn read_positioned(&mut self, data: &[(u64, bool)]) -> Vec<(u64, LtcFrame)> {
+        data.iter()
+            .filter_map(|&(position, bit)| self.push(position, bit))
+            .collect()
+    }
+
+    /// Reads a single bit that ended at sample `position`, and returns the frame it completed
+    /// together with the sample position at which that frame started.
+    pub fn push(&mut self, position: u64, bit: bool) -> Option<(u64, LtcFrame)> {
+        let mut result: Option<(u64, LtcFrame)> = None;
+        self.buffer = self.buffer << 1 | bit as u128;
+        self.buffer_length = self.buffer_length.saturating_add(1);
+        if self.buffer_length == 1 {
+            self.first_position = position;
+            self.longest_bit = 0;
+        } else {
+            self.longest_bit = self.longest_bit.max(position.saturating_sub(self.last_position));
+        }
+        self.last_position = position;
+        if self.buffer_length >= 16 && self.buffer & LTC_FRAME_HEADER_MASK == LTC_FRAME_HEADER {
+            let bit_length = position.saturating_sub(self.first_position) / (LTC_FRAME_LENGTH as u64 - 1);
+            // The signal dropped out in the middle of the frame if one of its bits took as long
+            // as ten of the others.
+            let interrupted = self.longest_bit > 10 * bit_length.max(1);
+            if self.buffer_length == LTC_FRAME_LENGTH && !interrupted {
+                let mut bits = [false; LTC_FRAME_LENGTH];
+                for (index, bit) in bits.iter_mut().enumerate() {
+                    *bit = self.buffer >> (LTC_FRAME_LENGTH - 1 - index) & 1 == 1;
+                }
+                let frame = LtcFrame::read(&bits);
+                let frame_id = frame.frame() as u128;
+                if frame_id < self.last_frame_count {
+                    self.frame_rate = Some(self.last_frame_count + 1);
                     self.last_frame_count = frame_id;
-                    result = Some(frame);
-                } else {
-                    self.last_frame_count = 0;
-                    self.frame_rate = None;
                 }
-                self.buffer.clear();
+                self.last_frame_count = frame_id;
+                // A frame right after a dropout does not start where the last sync word ended,
+                // but one bit before its first bit.
+                let start = if self.first_position.saturating_sub(self.buffer_position) > 2 * bit_length {
+                    self.first_position - bit_length
+                } else {
+                    self.buffer_position
+                };
+                result = Some((start, frame));
+            } else {
+                self.last_frame_count = 0;
+                self.frame_rate = None;
             }
-            self.buffer.push(bit);
-            result
-        }).collect();
+            self.buffer_length = 0;
+            self.buffer_position = self.last_position;
+        }
+        result
     }
-}
\ No newline at end of file
+}
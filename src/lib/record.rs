@@ -0,0 +1,125 @@
+use std::io::{Seek, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::thread::{self, JoinHandle};
+
+use cpal::Stream;
+use cpal::traits::StreamTrait;
+
+use crate::audio::{build_interleaved_input_stream, InputConfig};
+use crate::bwf::{BwfTimecode, BwfWriter};
+use crate::decoder::{DecodedFrame, Decoder, DecoderOptions};
+use crate::error::Error;
+use crate::event::LtcEvent;
+
+/// A running recording of an input stream to a broadcast wave file, see [`connect_recorder`].
+pub struct Recorder {
+    stream: Stream,
+    writer: JoinHandle<Result<Option<BwfTimecode>, Error>>,
+    length: Arc<AtomicU64>,
+}
+
+/// Records all channels of an input stream to `writer` as a broadcast wave file with 32 bit
+/// float samples, and decodes LTC from the channel of `config` while doing so. Everything the
+/// [`Decoder`] finds is reported to `callback`, errors of the running stream to
+/// `error_callback`.
+///
+/// As soon as the decoder knows the frame rate, the time of day of the first sample is
+/// computed from the frames decoded until then and written to the time reference of the file,
+/// see [`BwfTimecode::from_frames`]. The file is written on its own thread, so the audio
+/// thread never waits for the disk.
+pub fn connect_recorder<W, Callback, CallbackError>(
+    device: &cpal::Device,
+    config: impl Into<InputConfig>,
+    options: DecoderOptions,
+    writer: W,
+    callback: Callback,
+    error_callback: CallbackError,
+) -> Result<Recorder, Error>
+    where W: Write + Seek + Send + 'static,
+          Callback: Fn(LtcEvent) + Send + 'static,
+          CallbackError: Fn(Error) + Send + 'static,
+{
+    let config = config.into();
+    let channels = config.config.channels();
+    let sample_rate = config.config.sample_rate().0;
+    if config.channel >= channels {
+        return Err(Error::NoChannel { channel: config.channel, channels });
+    }
+    let channel = config.channel as usize;
+    let mut bwf = BwfWriter::new(writer, channels, sample_rate)?;
+    let (sender, receiver) = mpsc::channel::<Vec<f32>>();
+    let length = Arc::new(AtomicU64::new(0));
+    let writer_length = length.clone();
+
+    let writer = thread::spawn(move || {
+        let mut decoder = Decoder::new(options);
+        let mut frames: Vec<DecodedFrame> = vec![];
+        let mut timecode = None;
+        for block in receiver {
+            bwf.write(&block)?;
+            writer_length.store(bwf.len(), Ordering::Relaxed);
+            let data: Vec<f32> = block.iter().skip(channel).step_by(channels as usize).copied().collect();
+            for event in decoder.decode(&data) {
+                if let (None, LtcEvent::Frame(frame)) = (&timecode, &event) {
+                    frames.push(frame.clone());
+                }
+                callback(event);
+            }
+            if timecode.is_none() && frames.iter().any(|it| it.fps > 0) {
+                timecode = BwfTimecode::from_frames(&frames, sample_rate);
+                if let Some(timecode) = &timecode {
+                    bwf.set_time_reference(timecode.time_reference)?;
+                }
+            }
+        }
+        bwf.finish()?;
+        Ok(timecode)
+    });
+
+    let stream = build_interleaved_input_stream(
+        device,
+        &config,
+        move |data| {
+            // Only fails once the writer gave up, which `finish` reports.
+            let _ = sender.send(data.to_vec());
+        },
+        error_callback,
+    )?;
+    Ok(Recorder { stream, writer, length })
+}
+
+impl Recorder {
+    /// Starts or resumes recording.
+    pub fn play(&self) -> Result<(), Error> {
+        Ok(self.stream.play()?)
+    }
+
+    /// Pauses recording, the file simply continues when it is resumed.
+    pub fn pause(&self) -> Result<(), Error> {
+        Ok(self.stream.pause()?)
+    }
+
+    /// Number of sample frames written so far.
+    pub fn len(&self) -> u64 {
+        self.length.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the file is still being written. Writing stops early if it fails, `finish`
+    /// returns the reason.
+    pub fn is_recording(&self) -> bool {
+        !self.writer.is_finished()
+    }
+
+    /// Stops the stream, writes what is left of the recording and completes the file. Returns
+    /// the timecode the file was stamped with, or `None` if no timecode was found.
+    pub fn finish(self) -> Result<Option<BwfTimecode>, Error> {
+        drop(self.stream);
+        self.writer.join().expect("recorder thread panicked")
+    }
+}
@@ -0,0 +1,156 @@
+use std::fmt::Write;
+
+use crate::continuity::Continuity;
+use crate::decoder::DecodedFrame;
+use crate::edl::{write_edl, EdlEvent};
+use crate::error::Error;
+use crate::event::LtcEvent;
+use crate::format::TimecodeFormat;
+use crate::source::SourceEvents;
+use crate::timecode::Timecode;
+
+/// A stretch of a recording in which the timecode runs continuously.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment {
+    /// Timecode of the first frame.
+    pub start: Timecode,
+    /// Timecode right after the last frame.
+    pub end: Timecode,
+    /// Sample at which the first frame starts.
+    pub start_sample: u64,
+    /// Sample right after the last frame.
+    pub end_sample: u64,
+    /// Number of frames decoded in the segment.
+    pub frames: u64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::decoder::fps"))]
+    pub fps: u128,
+    pub drop: bool,
+}
+
+/// The continuous segments of a recording, see [`split_segments`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segments {
+    pub sample_rate: u32,
+    pub segments: Vec<Segment>,
+}
+
+/// Splits what was decoded from a whole recording into segments of continuous timecode. A new segment
+/// starts where the timecode jumps or changes its frame rate, and after the signal was lost.
+/// Interpolated frames are ignored, and so are repeated, skipped and misnumbered frames as long
+/// as the timecode continues after them.
+///
+/// The decoder only knows the frame rate after the first full second of a signal, until then
+/// the rate of the neighbouring segments is assumed.
+pub fn split_segments<E: SourceEvents>(events: E) -> Result<Segments, Error> {
+    let sample_rate = events.sample_rate();
+    let mut groups: Vec<Vec<DecodedFrame>> = vec![];
+    let mut current: Vec<DecodedFrame> = vec![];
+    for event in events {
+        match event? {
+            LtcEvent::Frame(frame) if !frame.frame.is_interpolated() => current.push(frame),
+            LtcEvent::Frame(_) => {}
+            LtcEvent::Discontinuity { continuity: Continuity::Jumped(_) | Continuity::RateChanged { .. }, .. } => {
+                // Reported right after the frame that broke the sequence.
+                if let Some(frame) = current.pop() {
+                    groups.push(std::mem::replace(&mut current, vec![frame]));
+                }
+            }
+            LtcEvent::Discontinuity { .. } => {}
+            LtcEvent::SignalLost { .. } | LtcEvent::Locked { .. } => {
+                groups.push(std::mem::take(&mut current));
+            }
+        }
+    }
+    groups.push(current);
+    groups.retain(|it| !it.is_empty());
+
+    let rates: Vec<Option<(u128, bool)>> = groups.iter()
+        .map(|frames| frames.iter().rev().find(|it| it.fps > 0).map(|it| (it.fps, it.frame.is_drop())))
+        .collect();
+    let segments = groups.iter().enumerate().map(|(index, frames)| {
+        let (fps, drop) = rates[index].or_else(|| rates[..index].iter().rev().flatten().next().copied())
+            .or_else(|| rates[index..].iter().flatten().next().copied())
+            .unwrap_or((0, false));
+        let (first, last) = (&frames[0], &frames[frames.len() - 1]);
+        let (num, den) = if drop { (1001, 1000) } else { (1, 1) };
+        let period = (sample_rate as u128 * num).checked_div(fps * den).unwrap_or(0) as u64;
+        Segment {
+            start: first.frame.timecode(),
+            end: last.frame.timecode().next(fps),
+            start_sample: first.position,
+            end_sample: last.position + period,
+            frames: frames.len() as u64,
+            fps,
+            drop,
+        }
+    }).collect();
+    Ok(Segments { sample_rate, segments })
+}
+
+impl Segments {
+    /// The segments as CSV with a header line, timecodes are written in `format`.
+    pub fn csv(&self, format: &TimecodeFormat) -> String {
+        let mut csv = String::new();
+        let _ = writeln!(csv, "start,end,start_sample,end_sample,frames,fps,drop");
+        for segment in &self.segments {
+            let _ = writeln!(
+                csv, "{},{},{},{},{},{},{}",
+                format.format(&segment.start, segment.fps), format.format(&segment.end, segment.fps),
+                segment.start_sample, segment.end_sample, segment.frames, segment.fps, segment.drop,
+            );
+        }
+        csv
+    }
+
+    /// The segments as a CMX3600 edit decision list with one event per segment, whose source
+    /// is the timecode of the segment on `reel`. The record side places the segments where
+    /// they are in the recording, at the frame rate of the first segment.
+    pub fn edl(&self, title: &str, reel: &str) -> String {
+        let (fps, drop) = self.segments.first().map(|it| (it.fps, it.drop)).unwrap_or((0, false));
+        let (num, den) = if drop { (1001, 1000) } else { (1, 1) };
+        let record = |sample: u64| Timecode::from_frame_count(
+            (sample as u128 * fps * den).checked_div(self.sample_rate as u128 * num).unwrap_or(0) as u64,
+            fps,
+            drop,
+        );
+        let events: Vec<EdlEvent> = self.segments.iter().map(|segment| EdlEvent {
+            reel,
+            track: "AA",
+            source_in: segment.start,
+            source_out: segment.end,
+            record_in: record(segment.start_sample),
+            record_out: record(segment.end_sample),
+            clip_name: None,
+            comments: vec![format!("SAMPLES {} TO {} AT {} HZ", segment.start_sample, segment.end_sample, self.sample_rate)],
+        }).collect();
+        write_edl(title, drop, &events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{read_test_file, test_source, Err};
+    use crate::{decode_source, split_segments, DecoderOptions, Timecode, TimecodeFormat};
+
+    #[test]
+    fn segments() -> Result<(), Err> {
+        // The recording twice in a row, so the timecode jumps back to where it started.
+        let samples = read_test_file()?;
+        let source = test_source(&[samples.as_slice(), &samples].concat());
+        let segments = split_segments(decode_source(source, DecoderOptions::default()))?;
+        assert_eq!(segments.segments.len(), 2);
+        let (first, second) = (&segments.segments[0], &segments.segments[1]);
+        assert_eq!((first.start, first.end), (Timecode::new(19, 12, 10, 7, false), Timecode::new(19, 12, 11, 10, false)));
+        assert_eq!((first.start_sample, first.frames, first.fps), (2653, 28, 25));
+        assert_eq!(second.start, first.start);
+        assert!(second.start_sample.abs_diff(samples.len() as u64 + 2653) < 100);
+
+        let csv = segments.csv(&TimecodeFormat::Smpte);
+        assert!(csv.starts_with("start,end,start_sample,end_sample,frames,fps,drop\n19:12:10:07,19:12:11:10,2653,"));
+        let edl = segments.edl("TAPE", "tape 1");
+        assert!(edl.contains("001  TAPE_1   AA    C        19:12:10:07 19:12:11:10 00:00:00:00 00:00:01:"));
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::VecDeque;
+
+use crate::decoder::{Decoder, DecoderOptions};
+use crate::error::Error;
+use crate::event::LtcEvent;
+
+/// Audio that is read rather than pushed by a device, like a file. A source delivers the
+/// samples of a single channel.
+pub trait SampleSource {
+    fn sample_rate(&self) -> u32;
+
+    /// Appends up to `samples` samples to `buffer`, and returns how many were appended. Returns
+    /// 0 at the end of the input.
+    fn read(&mut self, buffer: &mut Vec<f32>, samples: usize) -> Result<usize, Error>;
+}
+
+impl<S: SampleSource + ?Sized> SampleSource for Box<S> {
+    fn sample_rate(&self) -> u32 {
+        (**self).sample_rate()
+    }
+
+    fn read(&mut self, buffer: &mut Vec<f32>, samples: usize) -> Result<usize, Error> {
+        (**self).read(buffer, samples)
+    }
+}
+
+/// Events decoded from all of a [`SampleSource`], by [`decode_source`] or
+/// [`decode_source_parallel`](crate::decode_source_parallel).
+pub trait SourceEvents: Iterator<Item = Result<LtcEvent, Error>> {
+    fn sample_rate(&self) -> u32;
+
+    /// Number of samples decoded so far.
+    fn position(&self) -> u64;
+
+    /// Number of biphase mark coding violations in the signal so far.
+    fn biphase_errors(&self) -> u64;
+}
+
+impl<E: SourceEvents + ?Sized> SourceEvents for Box<E> {
+    fn sample_rate(&self) -> u32 {
+        (**self).sample_rate()
+    }
+
+    fn position(&self) -> u64 {
+        (**self).position()
+    }
+
+    fn biphase_errors(&self) -> u64 {
+        (**self).biphase_errors()
+    }
+}
+
+/// Iterator over the events decoded from a [`SampleSource`], see [`decode_source`].
+pub struct SourceDecoder<S> {
+    source: S,
+    decoder: Decoder,
+    buffer: Vec<f32>,
+    pending: VecDeque<LtcEvent>,
+    done: bool,
+}
+
+/// Decodes all of `source`, one second of samples at a time. Positions of the events are
+/// sample offsets from the start of the source.
+pub fn decode_source<S: SampleSource>(source: S, options: DecoderOptions) -> SourceDecoder<S> {
+    SourceDecoder {
+        source,
+        decoder: Decoder::new(options),
+        buffer: vec![],
+        pending: VecDeque::new(),
+        done: false,
+    }
+}
+
+impl<S: SampleSource> SourceDecoder<S> {
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Number of samples decoded so far.
+    pub fn position(&self) -> u64 {
+        self.decoder.position()
+    }
+
+    pub fn decoder(&self) -> &Decoder {
+        &self.decoder
+    }
+}
+
+impl<S: SampleSource> Iterator for SourceDecoder<S> {
+    type Item = Result<LtcEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() && !self.done {
+            self.buffer.clear();
+            let block = self.source.sample_rate().max(1) as usize;
+            match self.source.read(&mut self.buffer, block) {
+                Ok(0) => self.done = true,
+                Ok(_) => self.pending.extend(self.decoder.decode(&self.buffer)),
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+impl<S: SampleSource> SourceEvents for SourceDecoder<S> {
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn position(&self) -> u64 {
+        self.decoder.position()
+    }
+
+    fn biphase_errors(&self) -> u64 {
+        self.decoder.biphase_errors()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{read_test_file, source_frames, test_source, Err};
+
+    #[test]
+    fn dropout() -> Result<(), Err> {
+        let samples = read_test_file()?;
+        let expected = source_frames(test_source(&samples))?;
+        assert_eq!(expected.len(), 28);
+
+        // A fifth of a second of silence in the middle of a frame. Only that frame is lost, the
+        // frames around it decode as if there was no gap.
+        let (start, length) = (44150, 17640);
+        let mut gap = samples[..start].to_vec();
+        gap.extend(vec![0.0; length]);
+        gap.extend(&samples[start..]);
+        let period = expected[1].0 - expected[0].0;
+        let expected: Vec<_> = expected.into_iter()
+            .filter(|&(position, _)| position + period <= start as u64 || position >= start as u64)
+            .map(|(position, timecode)| match position >= start as u64 {
+                true => (position + length as u64, timecode),
+                false => (position, timecode),
+            })
+            .collect();
+        assert_eq!(expected.len(), 27);
+        let decoded = source_frames(test_source(&gap))?;
+        for frame in &expected {
+            assert!(decoded.iter().any(|it| it.1 == frame.1 && it.0.abs_diff(frame.0) < 10), "{:?} not decoded", frame);
+        }
+        Ok(())
+    }
+}
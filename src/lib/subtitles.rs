@@ -0,0 +1,106 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::decoder::DecodedFrame;
+use crate::format::TimecodeFormat;
+
+/// Kind of subtitle file written by [`write_subtitles`].
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub enum SubtitleFormat {
+    #[default]
+    WebVtt,
+    Srt,
+}
+
+impl FromStr for SubtitleFormat {
+    type Err = String;
+
+    /// Parses `vtt`, `webvtt` or `srt`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "vtt" | "webvtt" => Ok(SubtitleFormat::WebVtt),
+            "srt" => Ok(SubtitleFormat::Srt),
+            other => Err(format!("unknown subtitle format \"{}\", expected vtt or srt", other)),
+        }
+    }
+}
+
+/// Writes a subtitle file with one cue per frame in `frames`, which shows the timecode of the
+/// frame, written in `format`, from the sample at which the frame starts until the next frame.
+/// A cue is at most one frame period long, so the timecode disappears while there is no
+/// signal. `frames` must be in order, and `sample_rate` is the rate of the audio they were
+/// decoded from.
+pub fn write_subtitles(
+    frames: &[DecodedFrame],
+    sample_rate: u32,
+    subtitle_format: SubtitleFormat,
+    format: &TimecodeFormat,
+) -> String {
+    let (fps, drop) = frames.iter().rev()
+        .find(|it| it.fps > 0)
+        .map(|it| (it.fps, it.frame.is_drop()))
+        .unwrap_or((25, false));
+    let (num, den) = if drop { (1001, 1000) } else { (1, 1) };
+    let period = (sample_rate as u128 * num / (fps * den)) as u64;
+    let millis = |sample: u64| (sample as u128 * 1000).checked_div(sample_rate as u128).unwrap_or(0);
+    let time = |sample: u64| {
+        let millis = millis(sample);
+        let separator = if subtitle_format == SubtitleFormat::Srt { ',' } else { '.' };
+        format!(
+            "{:02}:{:02}:{:02}{}{:03}",
+            millis / 3_600_000, millis / 60_000 % 60, millis / 1000 % 60, separator, millis % 1000,
+        )
+    };
+
+    let mut subtitles = String::new();
+    if subtitle_format == SubtitleFormat::WebVtt {
+        subtitles.push_str("WEBVTT\n");
+    }
+    let mut cue = 0;
+    for (index, frame) in frames.iter().enumerate() {
+        let mut end = frame.position + period;
+        if let Some(next) = frames.get(index + 1) {
+            end = end.min(next.position);
+        }
+        if millis(end) == millis(frame.position) {
+            continue;
+        }
+        let text = format.format(&frame.frame.timecode(), if frame.fps > 0 { frame.fps } else { fps });
+        cue += 1;
+        if subtitle_format == SubtitleFormat::Srt {
+            let _ = writeln!(subtitles, "{}", cue);
+        } else {
+            subtitles.push('\n');
+        }
+        let _ = writeln!(subtitles, "{} --> {}", time(frame.position), time(end));
+        let _ = writeln!(subtitles, "{}", text);
+        if subtitle_format == SubtitleFormat::Srt {
+            subtitles.push('\n');
+        }
+    }
+    subtitles
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{read_test_file, test_source, Err};
+    use crate::{decode_source, write_subtitles, DecoderOptions, LtcEvent, SubtitleFormat, TimecodeFormat};
+
+    #[test]
+    fn subtitles() -> Result<(), Err> {
+        let samples = read_test_file()?;
+        let frames = decode_source(test_source(&samples), DecoderOptions::default())
+            .filter_map(|event| event.map(LtcEvent::into_frame).transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let vtt = write_subtitles(&frames, 88200, SubtitleFormat::WebVtt, &TimecodeFormat::Smpte);
+        assert!(vtt.starts_with("WEBVTT\n\n00:00:00.030 --> 00:00:00.070\n19:12:10:07\n\n00:00:00.070 --> 00:00:00.110\n"));
+        // The last frame lasts one frame period.
+        assert!(vtt.ends_with("\n\n00:00:01.109 --> 00:00:01.149\n19:12:11:09\n"));
+        let srt = write_subtitles(&frames, 88200, SubtitleFormat::Srt, &"%H.%M.%S.%F".parse()?);
+        assert!(srt.starts_with("1\n00:00:00,030 --> 00:00:00,070\n19.12.10.07\n\n2\n"));
+        assert!(srt.ends_with("28\n00:00:01,109 --> 00:00:01,149\n19.12.11.09\n\n"));
+        assert_eq!("SRT".parse::<SubtitleFormat>(), Ok(SubtitleFormat::Srt));
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+use std::ffi::OsStr;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::bwf::BwfTimecode;
+use crate::edl::{write_edl, EdlEvent};
+use crate::error::Error;
+use crate::event::LtcEvent;
+use crate::format::TimecodeFormat;
+use crate::source::SourceEvents;
+use crate::timecode::Timecode;
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+const DAY: u128 = 86400 * NANOS_PER_SECOND;
+
+/// A recording placed on the time of day by its timecode, see [`locate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recording {
+    pub name: String,
+    /// Length of the recording in samples.
+    pub samples: u64,
+    pub timecode: BwfTimecode,
+}
+
+/// Where a recording sits on the common timeline of a [`SyncReport`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyncEntry {
+    pub name: String,
+    pub sample_rate: u32,
+    /// Length of the recording in samples.
+    pub samples: u64,
+    /// Samples since midnight at the first sample, like the BWF time reference.
+    pub time_reference: u64,
+    /// Frame rate of the timecode of this recording.
+    #[cfg_attr(feature = "serde", serde(with = "crate::decoder::fps"))]
+    pub fps: u128,
+    pub drop: bool,
+    /// Timecode at the first sample, at the frame rate of this recording.
+    pub start: Timecode,
+    /// Timecode right after the last sample, at the frame rate of this recording.
+    pub end: Timecode,
+    /// How far after the start of the timeline the recording starts, in its own samples.
+    pub offset_samples: u64,
+    /// How far after the start of the timeline the recording starts, in frames of the timeline.
+    pub offset_frames: u64,
+    /// The timecode of this recording runs at a different frame rate than the timeline.
+    pub rate_mismatch: bool,
+    /// Indices of the other recordings that overlap this one.
+    pub overlaps: Vec<usize>,
+}
+
+/// Recordings placed on a common timeline, which starts with the earliest recording and runs
+/// at the timecode frame rate of the first one. See [`sync`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SyncReport {
+    #[cfg_attr(feature = "serde", serde(with = "crate::decoder::fps"))]
+    pub fps: u128,
+    pub drop: bool,
+    /// Timecode at the start of the timeline.
+    pub start: Timecode,
+    /// One entry per recording, in the order they were given.
+    pub entries: Vec<SyncEntry>,
+}
+
+/// Computes where on the time of day a recording starts from what was decoded from all of it,
+/// see [`BwfTimecode::from_frames`]. Fails with [`Error::NoTimecode`] if no timecode was found.
+pub fn locate<E: SourceEvents>(name: impl Into<String>, mut events: E) -> Result<Recording, Error> {
+    let sample_rate = events.sample_rate();
+    let mut frames = vec![];
+    for event in &mut events {
+        if let LtcEvent::Frame(frame) = event? {
+            frames.push(frame);
+        }
+    }
+    let timecode = BwfTimecode::from_frames(&frames, sample_rate).ok_or(Error::NoTimecode)?;
+    Ok(Recording { name: name.into(), samples: events.position(), timecode })
+}
+
+fn nanos(samples: u64, sample_rate: u32) -> u128 {
+    (samples as u128 * NANOS_PER_SECOND).checked_div(sample_rate as u128).unwrap_or(0)
+}
+
+/// Number of whole frames in `nanos` nanoseconds. Drop-frame timecode counts real time at
+/// 1000/1001 of its nominal frame rate.
+fn frames(nanos: u128, fps: u128, drop: bool) -> u64 {
+    let (num, den) = if drop { (1001, 1000) } else { (1, 1) };
+    (nanos * fps * den / (NANOS_PER_SECOND * num)) as u64
+}
+
+/// Places `recordings` on a common timeline. Recordings are expected to start and end on the
+/// same day, a recording that runs past midnight is treated as ending at midnight.
+pub fn sync(recordings: &[Recording]) -> SyncReport {
+    let (fps, drop) = recordings.first()
+        .map(|it| (it.timecode.fps, it.timecode.drop))
+        .unwrap_or((0, false));
+    let spans: Vec<(u128, u128)> = recordings.iter().map(|recording| {
+        let timecode = &recording.timecode;
+        let start = nanos(timecode.time_reference, timecode.sample_rate);
+        (start, (start + nanos(recording.samples, timecode.sample_rate)).min(DAY))
+    }).collect();
+    let timeline = spans.iter().map(|&(start, _)| start).min().unwrap_or(0);
+
+    let entries = recordings.iter().zip(&spans).enumerate().map(|(index, (recording, &(start, end)))| {
+        let timecode = &recording.timecode;
+        let overlaps = spans.iter().enumerate()
+            .filter(|&(other, &(other_start, other_end))| other != index && other_start < end && start < other_end)
+            .map(|(other, _)| other)
+            .collect();
+        SyncEntry {
+            name: recording.name.clone(),
+            sample_rate: timecode.sample_rate,
+            samples: recording.samples,
+            time_reference: timecode.time_reference,
+            fps: timecode.fps,
+            drop: timecode.drop,
+            start: Timecode::from_frame_count(frames(start, timecode.fps, timecode.drop), timecode.fps, timecode.drop),
+            end: Timecode::from_frame_count(frames(end, timecode.fps, timecode.drop), timecode.fps, timecode.drop),
+            offset_samples: ((start - timeline) * timecode.sample_rate as u128 / NANOS_PER_SECOND) as u64,
+            offset_frames: frames(start - timeline, fps, drop),
+            rate_mismatch: (timecode.fps, timecode.drop) != (fps, drop),
+            overlaps,
+        }
+    }).collect();
+    SyncReport {
+        fps,
+        drop,
+        start: Timecode::from_frame_count(frames(timeline, fps, drop), fps, drop),
+        entries,
+    }
+}
+
+impl SyncReport {
+    /// The entries as CSV with a header line, timecodes are written in `format`.
+    pub fn csv(&self, format: &TimecodeFormat) -> String {
+        let escape = |field: &str| if field.contains([',', '"', '\n']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        };
+        let mut csv = String::new();
+        let _ = writeln!(csv, "name,sample_rate,samples,fps,drop,start,end,offset_samples,offset_frames,rate_mismatch,overlaps");
+        for entry in &self.entries {
+            let overlaps: Vec<&str> = entry.overlaps.iter().map(|&it| self.entries[it].name.as_str()).collect();
+            let _ = writeln!(
+                csv, "{},{},{},{},{},{},{},{},{},{},{}",
+                escape(&entry.name), entry.sample_rate, entry.samples, entry.fps, entry.drop,
+                escape(&format.format(&entry.start, entry.fps)), escape(&format.format(&entry.end, entry.fps)),
+                entry.offset_samples, entry.offset_frames, entry.rate_mismatch, escape(&overlaps.join(";")),
+            );
+        }
+        csv
+    }
+
+    /// The recordings as a CMX3600 edit decision list at the frame rate of the timeline, one
+    /// event per recording in the order they start. Each event places the whole recording on
+    /// the timeline at its time of day, which is also its source timecode, on a reel named after
+    /// the file name without its extension.
+    pub fn edl(&self, title: &str) -> String {
+        let mut order: Vec<&SyncEntry> = self.entries.iter().collect();
+        order.sort_by_key(|entry| entry.offset_frames);
+        let events: Vec<EdlEvent> = order.into_iter().map(|entry| {
+            // The start and end of the recording converted to the timeline rate. A recording
+            // that runs past midnight ends at midnight like in `sync`, written as 24:00:00:00 so
+            // the event does not end before it starts.
+            let day = Timecode::frames_per_day(self.fps, self.drop);
+            let start = frames(nanos(entry.time_reference, entry.sample_rate), self.fps, self.drop);
+            let end = frames(nanos(entry.time_reference + entry.samples, entry.sample_rate).min(DAY), self.fps, self.drop);
+            let start = Timecode::from_frame_count(start, self.fps, self.drop);
+            let end = if end >= day && day > 0 {
+                Timecode::new(24, 0, 0, 0, self.drop)
+            } else {
+                Timecode::from_frame_count(end, self.fps, self.drop)
+            };
+            let mut comments = vec![];
+            if entry.rate_mismatch {
+                comments.push(format!(
+                    "TIMECODE RATE {} FPS{} DIFFERS FROM TIMELINE", entry.fps, if entry.drop { " DF" } else { "" },
+                ));
+            }
+            EdlEvent {
+                reel: Path::new(&entry.name).file_stem().and_then(OsStr::to_str).unwrap_or(&entry.name),
+                track: "AA",
+                source_in: start,
+                source_out: end,
+                record_in: start,
+                record_out: end,
+                clip_name: Some(&entry.name),
+                comments,
+            }
+        }).collect();
+        write_edl(title, self.drop, &events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{read_test_file, test_source, Err};
+    use crate::{decode_source, locate, sync, BwfTimecode, DecoderOptions, Recording, Timecode, TimecodeFormat};
+
+    #[test]
+    fn offsets() -> Result<(), Err> {
+        let samples = read_test_file()?;
+        let full = locate("full.wav", decode_source(test_source(&samples), DecoderOptions::default()))?;
+        let cut = locate("cut.wav", decode_source(test_source(&samples[44100..]), DecoderOptions::default()))?;
+        let later = Recording {
+            name: "later, 30 fps".to_string(),
+            samples: 48000,
+            timecode: BwfTimecode { time_reference: 20 * 3600 * 48000, sample_rate: 48000, fps: 30, drop: false },
+        };
+        let report = sync(&[cut, full, later]);
+        assert_eq!((report.fps, report.drop), (25, false));
+
+        let [cut, full, later] = &report.entries[..] else { panic!("expected three entries") };
+        assert_eq!(full.offset_samples, 0);
+        assert_eq!(report.start, full.start);
+        // The positions of single frames jitter by a few dozen samples.
+        assert!(cut.offset_samples.abs_diff(44100) < 100);
+        assert_eq!(cut.offset_frames, 12);
+        assert_eq!((&full.overlaps, &cut.overlaps, &later.overlaps), (&vec![0], &vec![1], &vec![]));
+        assert_eq!((full.rate_mismatch, cut.rate_mismatch, later.rate_mismatch), (false, false, true));
+        assert_eq!(later.start, Timecode::new(20, 0, 0, 0, false));
+        assert_eq!(later.end, Timecode::new(20, 0, 1, 0, false));
+
+        let csv = report.csv(&TimecodeFormat::Smpte);
+        let line = csv.lines().nth(3).unwrap_or_default();
+        assert!(line.starts_with("\"later, 30 fps\",48000,48000,30,false,20:00:00:00,20:00:01:00,"));
+        assert!(line.ends_with(",true,"));
+        let edl = report.edl("SYNC");
+        assert!(edl.starts_with("TITLE: SYNC\nFCM: NON-DROP FRAME\n"));
+        assert!(edl.contains("001  FULL     AA    C        ") && edl.contains("002  CUT      AA    C        "));
+        assert!(edl.contains("003  LATER__3 AA    C        20:00:00:00 20:00:01:00 20:00:00:00 20:00:01:00\n\
+            * FROM CLIP NAME: later, 30 fps\n\
+            * TIMECODE RATE 30 FPS DIFFERS FROM TIMELINE\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn edl_at_midnight() {
+        let late = Recording {
+            name: "late.wav".to_string(),
+            samples: 2 * 48000,
+            timecode: BwfTimecode { time_reference: (24 * 3600 - 1) * 48000, sample_rate: 48000, fps: 25, drop: false },
+        };
+        let edl = sync(&[late]).edl("MIDNIGHT");
+        assert!(edl.contains("001  LATE     AA    C        23:59:59:00 24:00:00:00 23:59:59:00 24:00:00:00\n"));
+    }
+}
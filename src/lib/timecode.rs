@@ -0,0 +1,304 @@
+use core::fmt::Formatter;
+#[cfg(feature = "std")]
+use std::str::FromStr;
+
+/// An SMPTE timecode address, without any of the flags or userdata that travel with it in an
+/// LTC frame.
+///
+/// Frame rates are passed in as the nominal integer rate reported by
+/// [`LtcFrameReader::frame_rate`](crate::LtcFrameReader::frame_rate), so 29.97 fps drop-frame
+/// timecode uses a rate of 30 together with the drop flag.
+///
+/// Serialized as an object with the fields `hour`, `minute`, `second`, `frame` and `drop`.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timecode {
+    hour: u8,
+    minute: u8,
+    second: u8,
+    frame: u8,
+    drop: bool,
+}
+
+impl Timecode {
+    pub fn new(hour: u8, minute: u8, second: u8, frame: u8, drop: bool) -> Timecode {
+        Timecode {
+            hour,
+            minute,
+            second,
+            frame,
+            drop,
+        }
+    }
+
+    /// Converts a frame count since midnight into a timecode. Counts past the end of the day
+    /// wrap around.
+    pub fn from_frame_count(count: u64, fps: u128, drop: bool) -> Timecode {
+        let fps = fps as u64;
+        if fps == 0 {
+            return Timecode { drop, ..Timecode::default() };
+        }
+        let mut count = count % frames_per_day(fps, drop);
+        let dropped = dropped_frames(fps, drop);
+        if dropped > 0 {
+            let per_ten_minutes = fps * 600 - dropped * 9;
+            let per_minute = fps * 60 - dropped;
+            let tens = count / per_ten_minutes;
+            let rest = count % per_ten_minutes;
+            count += dropped * 9 * tens;
+            if rest > dropped {
+                count += dropped * ((rest - dropped) / per_minute);
+            }
+        }
+        Timecode {
+            hour: (count / (fps * 3600)) as u8,
+            minute: (count / (fps * 60) % 60) as u8,
+            second: (count / fps % 60) as u8,
+            frame: (count % fps) as u8,
+            drop,
+        }
+    }
+
+    /// Number of frames since midnight, honouring the drop-frame rule if the drop flag is set.
+    pub fn to_frame_count(&self, fps: u128) -> u64 {
+        let fps = fps as u64;
+        let minutes = self.hour as u64 * 60 + self.minute as u64;
+        let count = (minutes * 60 + self.second as u64) * fps + self.frame as u64;
+        count - dropped_frames(fps, self.drop) * (minutes - minutes / 10)
+    }
+
+    /// Returns the timecode `frames` frames later (or earlier, if negative), wrapping at
+    /// midnight.
+    pub fn offset(&self, frames: i64, fps: u128) -> Timecode {
+        let day = frames_per_day(fps as u64, self.drop) as i64;
+        if day == 0 {
+            return *self;
+        }
+        let count = (self.to_frame_count(fps) as i64 + frames).rem_euclid(day);
+        Timecode::from_frame_count(count as u64, fps, self.drop)
+    }
+
+    /// Number of frames in a day at the given frame rate.
+    pub fn frames_per_day(fps: u128, drop: bool) -> u64 {
+        frames_per_day(fps as u64, drop)
+    }
+
+    pub fn next(&self, fps: u128) -> Timecode {
+        self.offset(1, fps)
+    }
+
+    pub fn frame(&self) -> u8 {
+        self.frame
+    }
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+    pub fn is_drop(&self) -> bool {
+        self.drop
+    }
+}
+
+/// Frames skipped at the start of every minute not divisible by ten.
+fn dropped_frames(fps: u64, drop: bool) -> u64 {
+    if drop && fps.is_multiple_of(30) {
+        fps / 15
+    } else {
+        0
+    }
+}
+
+fn frames_per_day(fps: u64, drop: bool) -> u64 {
+    fps * 86400 - dropped_frames(fps, drop) * 24 * 54
+}
+
+pub(crate) fn frame_separator(timecode: &Timecode) -> char {
+    if timecode.is_drop() { ';' } else { ':' }
+}
+
+impl core::fmt::Display for Timecode {
+    /// Formats as `HH:MM:SS:FF`, or `HH:MM:SS;FF` for drop-frame timecode.
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}{}{:02}",
+               self.hour, self.minute, self.second, frame_separator(self), self.frame,
+        )
+    }
+}
+
+/// Why a string could not be parsed as a [`Timecode`].
+#[cfg(feature = "std")]
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum ParseTimecodeError {
+    Empty,
+    /// The string is neither `HH:MM:SS:FF`, `HH:MM:SS;FF`, `HH:MM:SS.FF` nor a frame count.
+    Format(String),
+    /// A field is not a number.
+    Number { field: &'static str, value: String },
+    /// A field is outside of the allowed range.
+    Range { field: &'static str, value: u64, min: u64, max: u64 },
+    /// The drop-frame timecode names a frame that is skipped, frames 0 and 1 at the start of
+    /// every minute that is not a multiple of ten.
+    DroppedLabel(Timecode),
+    /// Drop-frame timecode was asked for at a frame rate that has none, only multiples of 30 fps
+    /// drop frames.
+    NoDropFrame(u64),
+    /// A frame count was given without the frame rate needed to convert it.
+    MissingFrameRate(u64),
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for ParseTimecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseTimecodeError::Empty =>
+                write!(f, "timecode is empty"),
+            ParseTimecodeError::Format(value) =>
+                write!(f, "\"{}\" is not a timecode, expected HH:MM:SS:FF, HH:MM:SS;FF, HH:MM:SS.FF or FRAMES@FPS", value),
+            ParseTimecodeError::Number { field, value } =>
+                write!(f, "{} \"{}\" is not a number", field, value),
+            ParseTimecodeError::Range { field, value, min, max } =>
+                write!(f, "{} {} is out of range, must be from {} to {}", field, value, min, max),
+            ParseTimecodeError::DroppedLabel(timecode) =>
+                write!(f, "{} does not exist, drop-frame timecode skips frames 0 and 1 at the start of every minute except every tenth", timecode),
+            ParseTimecodeError::NoDropFrame(fps) =>
+                write!(f, "there is no drop-frame timecode at {} fps", fps),
+            ParseTimecodeError::MissingFrameRate(count) =>
+                write!(f, "frame count {} needs a frame rate, e.g. {}@25", count, count),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseTimecodeError {}
+
+#[cfg(feature = "std")]
+fn parse_field(field: &'static str, value: &str, max: u64) -> Result<u64, ParseTimecodeError> {
+    if value.is_empty() || !value.bytes().all(|it| it.is_ascii_digit()) {
+        return Err(ParseTimecodeError::Number { field, value: value.to_string() });
+    }
+    let number = value.parse::<u64>()
+        .map_err(|_| ParseTimecodeError::Range { field, value: u64::MAX, min: 0, max })?;
+    if number > max {
+        return Err(ParseTimecodeError::Range { field, value: number, min: 0, max });
+    }
+    Ok(number)
+}
+
+#[cfg(feature = "std")]
+impl FromStr for Timecode {
+    type Err = ParseTimecodeError;
+
+    /// Parses `HH:MM:SS:FF`, or drop-frame timecode as `HH:MM:SS;FF` or `HH:MM:SS.FF`. Frame
+    /// counts since midnight are written as `FRAMES@FPS`, with a `df` suffix for drop-frame,
+    /// e.g. `107892@30df`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseTimecodeError::Empty);
+        }
+        if let Some((count, rate)) = s.split_once('@') {
+            let count = parse_field("frame count", count, u64::MAX)?;
+            let lower = rate.to_ascii_lowercase();
+            let (rate, drop) = match lower.strip_suffix("df") {
+                Some(rate) => (rate, true),
+                None => (lower.as_str(), false),
+            };
+            let fps = parse_field("frame rate", rate, 120)?;
+            if fps == 0 {
+                return Err(ParseTimecodeError::Range { field: "frame rate", value: 0, min: 1, max: 120 });
+            }
+            if drop && dropped_frames(fps, drop) == 0 {
+                return Err(ParseTimecodeError::NoDropFrame(fps));
+            }
+            let day = frames_per_day(fps, drop);
+            if count >= day {
+                return Err(ParseTimecodeError::Range { field: "frame count", value: count, min: 0, max: day - 1 });
+            }
+            return Ok(Timecode::from_frame_count(count, fps as u128, drop));
+        }
+        if s.bytes().all(|it| it.is_ascii_digit()) {
+            return Err(ParseTimecodeError::MissingFrameRate(parse_field("frame count", s, u64::MAX)?));
+        }
+
+        let separator = s.rfind([':', ';', '.'])
+            .ok_or_else(|| ParseTimecodeError::Format(s.to_string()))?;
+        let drop = s[separator..].starts_with([';', '.']);
+        let fields: Vec<&str> = s[..separator].split(':').collect();
+        let [hour, minute, second] = fields.as_slice() else {
+            return Err(ParseTimecodeError::Format(s.to_string()));
+        };
+        let timecode = Timecode {
+            hour: parse_field("hours", hour, 23)? as u8,
+            minute: parse_field("minutes", minute, 59)? as u8,
+            second: parse_field("seconds", second, 59)? as u8,
+            frame: parse_field("frames", &s[separator + 1..], 119)? as u8,
+            drop,
+        };
+        // Without a frame rate only the labels dropped at every rate can be told apart, frames
+        // 2 and 3 are dropped at 60 fps as well.
+        if drop && timecode.second == 0 && !timecode.minute.is_multiple_of(10) && timecode.frame < 2 {
+            return Err(ParseTimecodeError::DroppedLabel(timecode));
+        }
+        Ok(timecode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "std")]
+    use crate::ParseTimecodeError;
+    use crate::Timecode;
+
+    use super::frames_per_day;
+
+    #[test]
+    fn drop_frame_count() {
+        let tc = Timecode::new(0, 1, 0, 2, true);
+        assert_eq!(tc.to_frame_count(30), 1800);
+        assert_eq!(Timecode::from_frame_count(1800, 30, true), tc);
+        assert_eq!(Timecode::new(0, 0, 59, 29, true).next(30), tc);
+        assert_eq!(Timecode::new(0, 9, 59, 29, true).next(30), Timecode::new(0, 10, 0, 0, true));
+        assert_eq!(Timecode::new(0, 0, 0, 0, false).offset(-1, 25), Timecode::new(23, 59, 59, 24, false));
+        for count in (0..frames_per_day(30, true)).step_by(97) {
+            assert_eq!(Timecode::from_frame_count(count, 30, true).to_frame_count(30), count);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn parse() {
+        assert_eq!("10:00:00:05".parse(), Ok(Timecode::new(10, 0, 0, 5, false)));
+        assert_eq!("01:08:59;28".parse(), Ok(Timecode::new(1, 8, 59, 28, true)));
+        assert_eq!("01:08:59.28".parse(), Ok(Timecode::new(1, 8, 59, 28, true)));
+        assert_eq!("1800@30df".parse(), Ok(Timecode::new(0, 1, 0, 2, true)));
+        assert_eq!("90000@25".parse(), Ok(Timecode::new(1, 0, 0, 0, false)));
+        assert_eq!("".parse::<Timecode>(), Err(ParseTimecodeError::Empty));
+        assert_eq!("1800".parse::<Timecode>(), Err(ParseTimecodeError::MissingFrameRate(1800)));
+        assert_eq!("10:00:05".parse::<Timecode>(), Err(ParseTimecodeError::Format("10:00:05".to_string())));
+        assert_eq!("10:61:00:00".parse::<Timecode>(), Err(ParseTimecodeError::Range { field: "minutes", value: 61, min: 0, max: 59 }));
+        assert_eq!("00:10:00;00".parse(), Ok(Timecode::new(0, 10, 0, 0, true)));
+        assert_eq!("00:01:00:00".parse(), Ok(Timecode::new(0, 1, 0, 0, false)));
+        for (dropped, timecode) in [
+            ("00:01:00;00", Timecode::new(0, 1, 0, 0, true)),
+            ("00:01:00;01", Timecode::new(0, 1, 0, 1, true)),
+            ("23:59:00.01", Timecode::new(23, 59, 0, 1, true)),
+        ] {
+            assert_eq!(dropped.parse::<Timecode>(), Err(ParseTimecodeError::DroppedLabel(timecode)));
+        }
+        assert_eq!(
+            "00:01:00;01".parse::<Timecode>().map_err(|it| it.to_string()),
+            Err("00:01:00;01 does not exist, drop-frame timecode skips frames 0 and 1 at the start of every minute except every tenth".to_string()),
+        );
+        assert_eq!("90000@25df".parse::<Timecode>(), Err(ParseTimecodeError::NoDropFrame(25)));
+        assert_eq!("1800@60df".parse(), Ok(Timecode::new(0, 0, 30, 0, true)));
+        assert_eq!(
+            "10:0x:00:00".parse::<Timecode>().map_err(|it| it.to_string()),
+            Err("minutes \"0x\" is not a number".to_string()),
+        );
+    }
+}
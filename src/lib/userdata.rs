@@ -0,0 +1,146 @@
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, FixedOffset, NaiveDate, TimeZone};
+
+#[cfg(feature = "chrono")]
+use crate::timecode::Timecode;
+
+/// The 32 userdata bits of a frame, decoded according to its binary group flags.
+///
+/// Serialized as an object with a single snake case key, e.g. `{"unspecified": 0}` or
+/// `{"characters": [66, 67, 68, 69]}`.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Userdata {
+    /// No format specified, the eight binary groups as one number, binary group 1 in the lowest
+    /// four bits.
+    Unspecified(u32),
+    /// Four characters of an 8-bit ISO character set, binary groups 1 and 2 form the first one.
+    Characters([u8; 4]),
+    /// Date and time zone according to SMPTE 309M.
+    DateTimeZone(DateTimeZone),
+    /// Page/line multiplex, as one number like [`Unspecified`](Self::Unspecified).
+    PageLine(u32),
+}
+
+/// Date and time zone as carried in the binary groups according to SMPTE 309M.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DateTimeZone {
+    year: u8,
+    month: u8,
+    day: u8,
+    time_zone: u8,
+}
+
+impl Userdata {
+    /// Decodes the binary groups `groups`, with binary group flags `bgf0` and `bgf2`.
+    pub fn decode(groups: [u8; 8], bgf0: bool, bgf2: bool) -> Userdata {
+        let value = groups.iter().rev()
+            .fold(0u32, |value, &group| value << 4 | group as u32);
+        match (bgf2, bgf0) {
+            (false, false) => Userdata::Unspecified(value),
+            (false, true) => Userdata::Characters([
+                groups[0] | groups[1] << 4,
+                groups[2] | groups[3] << 4,
+                groups[4] | groups[5] << 4,
+                groups[6] | groups[7] << 4,
+            ]),
+            (true, false) => Userdata::DateTimeZone(DateTimeZone {
+                day: groups[0] + groups[1] * 10,
+                month: groups[2] + groups[3] * 10,
+                year: groups[4] + groups[5] * 10,
+                time_zone: (groups[6] | groups[7] << 4) & 0x3F,
+            }),
+            (true, true) => Userdata::PageLine(value),
+        }
+    }
+}
+
+impl DateTimeZone {
+    /// Two-digit year.
+    pub fn year(&self) -> u8 {
+        self.year
+    }
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+    /// The SMPTE 309M time zone code.
+    pub fn time_zone(&self) -> u8 {
+        self.time_zone
+    }
+
+    /// Offset from UTC in minutes of the time zone code, `None` for the time precision classes,
+    /// the user defined offset and unassigned codes.
+    pub fn utc_offset_minutes(&self) -> Option<i32> {
+        let (high, low) = ((self.time_zone >> 4) as i32, (self.time_zone & 0x0F) as i32);
+        match (high, low) {
+            // Chatham Islands, the only quarter-hour zone.
+            (3, 2) => Some(12 * 60 + 45),
+            // Whole hours are written as BCD: 0x01 to 0x12 count west of UTC, 0x13 to 0x25
+            // count back east from +13 hours.
+            (0..=2, 0..=9) => match high * 10 + low {
+                code @ 0..=12 => Some(-code * 60),
+                code @ 13..=25 => Some((26 - code) * 60),
+                _ => None,
+            },
+            // Half hours use the codes that are not BCD: 0x0A to 0x1F count west from -0:30
+            // to -11:30, 0x2A to 0x3F count back east from +11:30 to +0:30.
+            (0 | 1, 10..=15) => Some(-((high * 6 + low - 10) * 60 + 30)),
+            (2 | 3, 10..=15) => Some((11 - (high - 2) * 6 - (low - 10)) * 60 + 30),
+            _ => None,
+        }
+    }
+
+    /// Combines the date and time zone with the time of day given by `timecode` into a calendar
+    /// timestamp. Years are taken to be in the 21st century. Returns `None` if the date is
+    /// invalid or the time zone has no known offset.
+    #[cfg(feature = "chrono")]
+    pub fn to_date_time(&self, timecode: Timecode, fps: u128) -> Option<DateTime<FixedOffset>> {
+        let offset = FixedOffset::east_opt(self.utc_offset_minutes()? * 60)?;
+        let nanos = (timecode.frame() as u128 * 1_000_000_000).checked_div(fps)
+            .unwrap_or(0) as u32;
+        let time = NaiveDate::from_ymd_opt(2000 + self.year as i32, self.month as u32, self.day as u32)?
+            .and_hms_nano_opt(timecode.hour() as u32, timecode.minute() as u32, timecode.second() as u32, nanos)?;
+        offset.from_local_datetime(&time).single()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Userdata;
+
+    #[test]
+    fn decode() {
+        let groups = [0x2, 0x4, 0x3, 0x4, 0x4, 0x4, 0x5, 0x4];
+        assert_eq!(Userdata::decode(groups, false, false), Userdata::Unspecified(0x45444342));
+        assert_eq!(Userdata::decode(groups, true, false), Userdata::Characters(*b"BCDE"));
+        assert_eq!(Userdata::decode(groups, true, true), Userdata::PageLine(0x45444342));
+
+        let date = match Userdata::decode([9, 1, 0, 1, 6, 2, 5, 2], false, true) {
+            Userdata::DateTimeZone(date) => date,
+            other => panic!("unexpected userdata {:?}", other),
+        };
+        assert_eq!((date.year(), date.month(), date.day()), (26, 10, 19));
+        assert_eq!(date.utc_offset_minutes(), Some(60));
+        let offsets = [(0x00, Some(0)), (0x05, Some(-300)), (0x14, Some(720)), (0x0A, Some(-30)), (0x0D, Some(-210)),
+            (0x1F, Some(-690)), (0x2A, Some(690)), (0x3A, Some(330)), (0x3F, Some(30)), (0x32, Some(765)),
+            (0x26, None), (0x38, None)];
+        for (code, offset) in offsets {
+            let zone = match Userdata::decode([9, 1, 0, 1, 6, 2, code & 0x0F, code >> 4], false, true) {
+                Userdata::DateTimeZone(date) => date,
+                other => panic!("unexpected userdata {:?}", other),
+            };
+            assert_eq!(zone.utc_offset_minutes(), offset, "time zone {:#04x}", code);
+        }
+        #[cfg(feature = "chrono")]
+        assert_eq!(
+            date.to_date_time(crate::Timecode::new(14, 32, 10, 5, false), 25)
+                .map(|it| (it.timestamp_millis(), it.offset().local_minus_utc())),
+            Some((1792416730200, 3600)),
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::error::Error;
+use crate::raw::PcmFormat;
+use crate::source::SampleSource;
+
+pub(crate) const FORMAT_PCM: u16 = 0x0001;
+pub(crate) const FORMAT_FLOAT: u16 = 0x0003;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Offset of the `TimeReference` field in the `bext` chunk of a broadcast wave file.
+pub(crate) const BEXT_TIME_REFERENCE: usize = 338;
+
+/// The sample format of a WAV file.
+#[derive(Eq, PartialEq, Clone, Copy, Debug)]
+pub struct WavFormat {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    /// Samples are IEEE floats rather than integers.
+    pub float: bool,
+}
+
+impl WavFormat {
+    /// Only called on formats [`read_format`] accepted.
+    fn pcm_format(&self) -> PcmFormat {
+        PcmFormat::wav(self.bits_per_sample, self.float).unwrap()
+    }
+
+    fn block_align(&self) -> usize {
+        self.pcm_format().bytes_per_sample() * self.channels as usize
+    }
+}
+
+/// Reads PCM samples from a WAV or broadcast wave (BWF) file.
+///
+/// Integer samples of 8, 16, 24 and 32 bits and float samples of 32 and 64 bits are
+/// supported, with any number of channels. One channel is read at a time, see
+/// [`set_channel`](Self::set_channel).
+pub struct WavReader<R> {
+    reader: R,
+    format: WavFormat,
+    channel: u16,
+    data_start: u64,
+    /// Number of sample frames in the data chunk.
+    length: u64,
+    position: u64,
+    time_reference: Option<u64>,
+    bytes: Vec<u8>,
+}
+
+impl WavReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        WavReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> WavReader<R> {
+    /// Reads the headers of a WAV file, and leaves `reader` at the first sample.
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let mut format = None;
+        let mut data = None;
+        let mut time_reference = None;
+        for chunk in read_chunks(&mut reader)? {
+            match &chunk.id {
+                b"fmt " => {
+                    reader.seek(SeekFrom::Start(chunk.start))?;
+                    format = Some(read_format(&mut reader, chunk.size)?);
+                }
+                b"bext" if chunk.size as usize >= BEXT_TIME_REFERENCE + 8 => {
+                    let mut bext = vec![0u8; BEXT_TIME_REFERENCE + 8];
+                    reader.seek(SeekFrom::Start(chunk.start))?;
+                    reader.read_exact(&mut bext)?;
+                    time_reference = Some(u64::from_le_bytes(
+                        bext[BEXT_TIME_REFERENCE..].try_into().unwrap(),
+                    ));
+                }
+                b"data" => data = Some((chunk.start, chunk.size)),
+                _ => {}
+            }
+        }
+
+        let format = format.ok_or_else(|| Error::InvalidFile("no fmt chunk".to_string()))?;
+        let (data_start, data_size) = data.ok_or_else(|| Error::InvalidFile("no data chunk".to_string()))?;
+        reader.seek(SeekFrom::Start(data_start))?;
+        Ok(WavReader {
+            reader,
+            format,
+            channel: 0,
+            data_start,
+            length: data_size / format.block_align() as u64,
+            position: 0,
+            time_reference,
+            bytes: vec![],
+        })
+    }
+
+    pub fn format(&self) -> WavFormat {
+        self.format
+    }
+
+    /// Number of samples per channel.
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Byte offset of the first sample in the file.
+    pub fn data_start(&self) -> u64 {
+        self.data_start
+    }
+
+    /// The `TimeReference` of a broadcast wave file: the sample count since midnight at which
+    /// the file starts.
+    pub fn time_reference(&self) -> Option<u64> {
+        self.time_reference
+    }
+
+    pub fn channel(&self) -> u16 {
+        self.channel
+    }
+
+    /// Selects the channel to read, counting from 0.
+    pub fn set_channel(&mut self, channel: u16) -> Result<(), Error> {
+        if channel >= self.format.channels {
+            return Err(Error::NoChannel { channel, channels: self.format.channels });
+        }
+        self.channel = channel;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read + Seek> SampleSource for WavReader<R> {
+    fn sample_rate(&self) -> u32 {
+        self.format.sample_rate
+    }
+
+    fn read(&mut self, buffer: &mut Vec<f32>, samples: usize) -> Result<usize, Error> {
+        let frames = (samples as u64).min(self.length - self.position) as usize;
+        let block_align = self.format.block_align();
+        self.bytes.resize(frames * block_align, 0);
+        self.reader.read_exact(&mut self.bytes)?;
+        self.position += frames as u64;
+
+        let format = self.format.pcm_format();
+        let offset = self.channel as usize * format.bytes_per_sample();
+        buffer.extend(self.bytes.chunks_exact(block_align)
+            .map(|frame| format.decode(&frame[offset..])));
+        Ok(frames)
+    }
+}
+
+/// A chunk of a RIFF file, `start` is the offset of its contents in the file.
+pub(crate) struct Chunk {
+    pub id: [u8; 4],
+    pub start: u64,
+    pub size: u64,
+}
+
+/// Lists the chunks of a RIFF WAVE file.
+pub(crate) fn read_chunks<R: Read + Seek>(reader: &mut R) -> Result<Vec<Chunk>, Error> {
+    let mut header = [0u8; 12];
+    reader.seek(SeekFrom::Start(0))?;
+    reader.read_exact(&mut header)?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Err(Error::InvalidFile("not a RIFF WAVE file".to_string()));
+    }
+    let end = reader.seek(SeekFrom::End(0))?;
+    let mut chunks = vec![];
+    let mut start = 12;
+    while start + 8 <= end {
+        let mut chunk = [0u8; 8];
+        reader.seek(SeekFrom::Start(start))?;
+        reader.read_exact(&mut chunk)?;
+        let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+        // A data chunk that was never finished may claim more than the file holds.
+        let size = size.min(end - start - 8);
+        chunks.push(Chunk { id: [chunk[0], chunk[1], chunk[2], chunk[3]], start: start + 8, size });
+        start += 8 + size + size % 2;
+    }
+    Ok(chunks)
+}
+
+fn read_format<R: Read>(reader: &mut R, size: u64) -> Result<WavFormat, Error> {
+    if size < 16 {
+        return Err(Error::InvalidFile("fmt chunk too short".to_string()));
+    }
+    let mut chunk = vec![0u8; size.min(40) as usize];
+    reader.read_exact(&mut chunk)?;
+    let u16_at = |offset: usize| u16::from_le_bytes([chunk[offset], chunk[offset + 1]]);
+    let mut tag = u16_at(0);
+    if tag == FORMAT_EXTENSIBLE && chunk.len() >= 26 {
+        // The first two bytes of the sub format GUID are the actual format tag.
+        tag = u16_at(24);
+    }
+    let format = WavFormat {
+        channels: u16_at(2),
+        sample_rate: u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]),
+        bits_per_sample: u16_at(14),
+        float: tag == FORMAT_FLOAT,
+    };
+    match (tag, format.bits_per_sample) {
+        _ if format.channels == 0 => Err(Error::InvalidFile("no channels".to_string())),
+        (FORMAT_PCM | FORMAT_FLOAT, bits) if PcmFormat::wav(bits, format.float).is_some() => Ok(format),
+        (FORMAT_PCM | FORMAT_FLOAT, bits) => Err(Error::InvalidFile(format!(
+            "unsupported {} bit {} samples", bits, if format.float { "float" } else { "integer" },
+        ))),
+        (tag, _) => Err(Error::InvalidFile(format!("unsupported format tag {:#06x}", tag))),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::Cursor;
+
+    use crate::tests::{read_test_file, Err};
+    use crate::{decode_source, Decoder, DecoderOptions, Error, LtcEvent, WavReader};
+
+    use super::{BEXT_TIME_REFERENCE, FORMAT_EXTENSIBLE, FORMAT_FLOAT, FORMAT_PCM};
+
+    /// A two channel file with silence on the first channel and `samples` on the second.
+    pub(crate) fn wav_file(tag: u16, bits: u16, sample_rate: u32, samples: &[f32], time_reference: Option<u64>) -> Vec<u8> {
+        let encode = |sample: f32| -> Vec<u8> {
+            match (tag, bits) {
+                (FORMAT_FLOAT, 32) => sample.to_le_bytes().to_vec(),
+                (FORMAT_FLOAT, _) => (sample as f64).to_le_bytes().to_vec(),
+                (_, 8) => vec![(sample * 127.0 + 128.0) as u8],
+                (_, bits) => {
+                    let value = (sample as f64 * ((1u64 << (bits - 1)) - 1) as f64) as i32;
+                    value.to_le_bytes()[0..bits as usize / 8].to_vec()
+                }
+            }
+        };
+        let mut data = vec![];
+        for &sample in samples {
+            data.extend(encode(0.0));
+            data.extend(encode(sample));
+        }
+
+        let mut fmt = vec![];
+        fmt.extend(if tag == FORMAT_FLOAT { FORMAT_FLOAT } else { FORMAT_EXTENSIBLE }.to_le_bytes());
+        fmt.extend(2u16.to_le_bytes());
+        fmt.extend(sample_rate.to_le_bytes());
+        fmt.extend((sample_rate * bits as u32 / 4).to_le_bytes());
+        fmt.extend((bits / 4).to_le_bytes());
+        fmt.extend(bits.to_le_bytes());
+        if tag != FORMAT_FLOAT {
+            fmt.extend(22u16.to_le_bytes());
+            fmt.extend(bits.to_le_bytes());
+            fmt.extend(3u32.to_le_bytes());
+            fmt.extend(FORMAT_PCM.to_le_bytes());
+            fmt.extend([0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71]);
+        }
+
+        let mut chunks: Vec<(&[u8; 4], Vec<u8>)> = vec![(b"fmt ", fmt)];
+        if let Some(time_reference) = time_reference {
+            let mut bext = vec![0u8; 602];
+            bext[BEXT_TIME_REFERENCE..BEXT_TIME_REFERENCE + 8].copy_from_slice(&time_reference.to_le_bytes());
+            chunks.push((b"bext", bext));
+        }
+        chunks.push((b"data", data));
+
+        let mut file = b"RIFF\0\0\0\0WAVE".to_vec();
+        for (id, chunk) in chunks {
+            file.extend(id);
+            file.extend((chunk.len() as u32).to_le_bytes());
+            file.extend(&chunk);
+            if chunk.len() % 2 == 1 {
+                file.push(0);
+            }
+        }
+        let size = (file.len() as u32 - 8).to_le_bytes();
+        file[4..8].copy_from_slice(&size);
+        file
+    }
+
+    #[test]
+    fn decode() -> Result<(), Err> {
+        let samples = read_test_file()?;
+        let mut decoder = Decoder::new(DecoderOptions::default());
+        let expected: Vec<_> = samples.chunks(48000)
+            .flat_map(|block| decoder.decode(block))
+            .filter_map(LtcEvent::into_frame)
+            .map(|frame| (frame.position, frame.frame.timecode()))
+            .collect();
+        assert!(!expected.is_empty());
+
+        for (tag, bits) in [(FORMAT_PCM, 8), (FORMAT_PCM, 16), (FORMAT_PCM, 24), (FORMAT_PCM, 32), (FORMAT_FLOAT, 32), (FORMAT_FLOAT, 64)] {
+            let mut reader = WavReader::new(Cursor::new(wav_file(tag, bits, 48000, &samples, Some(3_153_600_000))))?;
+            assert_eq!(reader.format().bits_per_sample, bits);
+            assert_eq!(reader.len(), samples.len() as u64);
+            assert_eq!(reader.time_reference(), Some(3_153_600_000));
+            assert!(matches!(reader.set_channel(2), Err(Error::NoChannel { channel: 2, channels: 2 })));
+            reader.set_channel(1)?;
+            let frames = decode_source(reader, DecoderOptions::default())
+                .filter_map(|event| event.map(LtcEvent::into_frame).transpose())
+                .map(|frame| frame.map(|frame| (frame.position, frame.frame.timecode())))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(frames, expected, "{} bit samples", bits);
+        }
+
+        let silent = WavReader::new(Cursor::new(wav_file(FORMAT_PCM, 16, 48000, &samples, None)))?;
+        assert_eq!(silent.time_reference(), None);
+        assert_eq!(decode_source(silent, DecoderOptions::default()).count(), 0);
+        assert!(matches!(WavReader::new(Cursor::new(b"RIFF\0\0\0\0AVI ".to_vec())), Err(Error::InvalidFile(_))));
+        Ok(())
+    }
+}